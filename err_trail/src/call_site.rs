@@ -0,0 +1,533 @@
+use core::fmt::{self, Display};
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use crate::{__Extra, __Noted, ErrLog, Level, NoneLog};
+
+/// Incremented by [`reset_call_sites`]. Each [`CallSite`] resets itself when it sees a new value.
+static EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+/// How many call sites of each [`Kind`] are tracked. Further call sites are not limited, with a
+/// warning the first time.
+const CAPACITY: usize = 128;
+
+/// The call sites of the inputs in this module, one table per [`Kind`], keyed by a hash of their
+/// location and kind.
+static CALL_SITES: [[Slot; CAPACITY]; Kind::TRACKED] =
+    [const { [const { Slot::new() }; CAPACITY] }; Kind::TRACKED];
+
+/// Whether the table of each [`Kind`] was full, so the warning is only logged once.
+static OVERFLOWED: [AtomicBool; Kind::TRACKED] = [const { AtomicBool::new(false) }; Kind::TRACKED];
+
+struct Slot {
+    key: AtomicUsize,
+    call_site: CallSite,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Slot {
+            key: AtomicUsize::new(0),
+            call_site: CallSite::new(),
+        }
+    }
+}
+
+/// Resets the state of every call site, e.g. between tests, so [`Once`] and the `_once` macros log
+/// again, [`Throttled`] and the `_throttled` macros start a new window, [`EveryN`] and the
+/// `_every_n` macros count from the start, and the `_escalating` methods de-escalate.
+pub fn reset_call_sites() {
+    EPOCH.fetch_add(1, Ordering::Release);
+}
+
+/// The state of one call site. The macros declare one as a `static` per expansion, the inputs look
+/// theirs up by location.
+#[doc(hidden)]
+pub struct CallSite {
+    epoch: AtomicUsize,
+    hits: AtomicUsize,
+    window: AtomicUsize,
+    suppressed: AtomicUsize,
+}
+
+impl CallSite {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        CallSite {
+            epoch: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+            window: AtomicUsize::new(0),
+            suppressed: AtomicUsize::new(0),
+        }
+    }
+
+    /// The state of the call site at `location`, or [`None`] if too many call sites are tracked.
+    /// Each kind of input has its own state, so they can be nested, e.g. `Once(Throttled::new(..))`.
+    fn at(location: &'static Location<'static>, kind: Kind) -> Option<&'static CallSite> {
+        let key = hash(location, kind);
+        let start = key % CAPACITY;
+        let call_sites = &CALL_SITES[kind as usize];
+        for i in 0..CAPACITY {
+            let slot = &call_sites[(start + i) % CAPACITY];
+            match slot
+                .key
+                .compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Some(&slot.call_site),
+                Err(existing) if existing == key => return Some(&slot.call_site),
+                Err(_) => {}
+            }
+        }
+        overflowed(&OVERFLOWED[kind as usize], CAPACITY, location, kind);
+        None
+    }
+
+    /// The state of the call site at `location` if it is already tracked.
+    fn existing(location: &'static Location<'static>, kind: Kind) -> Option<&'static CallSite> {
+        let key = hash(location, kind);
+        let start = key % CAPACITY;
+        let call_sites = &CALL_SITES[kind as usize];
+        for i in 0..CAPACITY {
+            let slot = &call_sites[(start + i) % CAPACITY];
+            match slot.key.load(Ordering::Acquire) {
+                0 => return None,
+                existing if existing == key => return Some(&slot.call_site),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Clears the state if [`reset_call_sites`] was called since it was last used.
+    fn sync(&self) {
+        let epoch = EPOCH.load(Ordering::Acquire);
+        if self.epoch.load(Ordering::Acquire) != epoch {
+            self.hits.store(0, Ordering::Relaxed);
+            self.window.store(0, Ordering::Relaxed);
+            self.suppressed.store(0, Ordering::Relaxed);
+            self.epoch.store(epoch, Ordering::Release);
+        }
+    }
+
+    /// `Some`, without a note, only the first time.
+    pub fn once(&self) -> Option<Option<Suppressed>> {
+        self.sync();
+        (self.hits.swap(1, Ordering::Relaxed) == 0).then_some(None)
+    }
+
+    /// `Some`, without a note, for the first of every `n` events.
+    pub fn every_n(&self, n: usize) -> Option<Option<Suppressed>> {
+        self.sync();
+        self.hits
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(n.max(1))
+            .then_some(None)
+    }
+
+    /// `Some` for the first `max` events of each window of `per`, noting how many were suppressed
+    /// in the previous windows. Always `Some` if there is no [`Clock`](crate::Clock), with a warning
+    /// the first time.
+    #[track_caller]
+    pub fn throttle(&self, per: Duration, max: usize) -> Option<Option<Suppressed>> {
+        self.sync();
+        let Some(now) = crate::clock::now_at(Location::caller()) else {
+            return Some(None);
+        };
+        // Milliseconds wrap on 32 bit targets, which is fine for windows shorter than 49 days.
+        let now = now.as_millis() as usize;
+        let hits = self.hits.fetch_add(1, Ordering::Relaxed);
+        if hits == 0
+            || now.wrapping_sub(self.window.load(Ordering::Relaxed)) >= per.as_millis() as usize
+        {
+            self.window.store(now, Ordering::Relaxed);
+            self.hits.store(1, Ordering::Relaxed);
+            let suppressed = self.suppressed.swap(0, Ordering::Relaxed);
+            Some((suppressed > 0).then_some(Suppressed(suppressed)))
+        } else if hits < max {
+            Some(None)
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Counts a failure, returning whether `threshold` failures were counted in a row within
+    /// `window` of the first, including this one. Once they were, it stays escalated until
+    /// [`succeed`], however long the failures go on. Without a [`Clock`](crate::Clock), failures
+    /// are counted until [`succeed`].
+    ///
+    /// [`succeed`]: CallSite::succeed
+    fn fail(
+        &self,
+        location: &'static Location<'static>,
+        threshold: usize,
+        window: Duration,
+    ) -> bool {
+        self.sync();
+        let hits = self.hits.fetch_add(1, Ordering::Relaxed);
+        if hits >= threshold {
+            // Capped, so the count cannot wrap around during a long outage.
+            self.hits.store(threshold, Ordering::Relaxed);
+            return true;
+        }
+        if let Some(now) = crate::clock::now_at(location) {
+            let now = now.as_millis() as usize;
+            if hits == 0
+                || now.wrapping_sub(self.window.load(Ordering::Relaxed))
+                    >= window.as_millis() as usize
+            {
+                self.window.store(now, Ordering::Relaxed);
+                self.hits.store(1, Ordering::Relaxed);
+                return threshold <= 1;
+            }
+        }
+        hits + 1 >= threshold
+    }
+
+    /// Resets the count of failures in a row.
+    fn succeed(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+    }
+}
+
+/// The level of a failure at `location` for the `_escalating` methods: `level`, or "error" once
+/// `threshold` failures were counted in a row within `window`, until the next success.
+pub(crate) fn escalate(
+    location: &'static Location<'static>,
+    level: Level,
+    threshold: usize,
+    window: Duration,
+) -> Level {
+    match CallSite::at(location, Kind::Escalating) {
+        Some(call_site) if call_site.fail(location, threshold, window) => Level::Error,
+        _ => level,
+    }
+}
+
+/// Resets the count of failures at `location` for the `_escalating` methods after a success.
+pub(crate) fn recover(location: &'static Location<'static>) {
+    if let Some(call_site) = CallSite::existing(location, Kind::Escalating) {
+        call_site.succeed();
+    }
+}
+
+/// Notes how many events were suppressed, e.g. "(suppressed 532 similar messages)".
+#[doc(hidden)]
+pub struct Suppressed(usize);
+
+impl Display for Suppressed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(suppressed {} similar messages)", self.0)
+    }
+}
+
+/// What a call site is tracked for.
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    Once,
+    Throttled,
+    EveryN,
+    Escalating,
+    /// Tracked by [`dedup`](crate::dedup) in its own table.
+    Dedup,
+}
+
+impl Kind {
+    /// How many kinds are tracked in [`CALL_SITES`].
+    const TRACKED: usize = Kind::Dedup as usize;
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Once => "once",
+            Kind::Throttled => "throttled",
+            Kind::EveryN => "every_n",
+            Kind::Escalating => "escalating",
+            Kind::Dedup => "dedup",
+        }
+    }
+}
+
+/// Warns the first time a table of call sites is full, at the call site that did not fit.
+pub(crate) fn overflowed(
+    flag: &AtomicBool,
+    capacity: usize,
+    location: &'static Location<'static>,
+    kind: Kind,
+) {
+    if !flag.swap(true, Ordering::Relaxed) {
+        log_at!(
+            warn,
+            WARN,
+            Warn,
+            location,
+            None,
+            None,
+            "more than {} call sites are tracked for {}, further call sites are not tracked",
+            capacity,
+            kind.name()
+        );
+    }
+}
+
+/// A non-zero FNV-1a hash of the file, line, column and kind.
+pub(crate) fn hash(location: &Location<'_>, kind: Kind) -> usize {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let line = location.line().to_le_bytes();
+    let column = location.column().to_le_bytes();
+    let bytes = location.file().bytes().chain(line).chain(column);
+    for byte in bytes.chain([kind as u8]) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+    }
+    (hash as usize).max(1)
+}
+
+/// Implements [`ErrLog`] and [`NoneLog`] for an input that wraps another input in `$inner`, and
+/// only passes on events when `$admit` is `Some`, with an optional note, and `$sample_rate` if
+/// sampled. `$admit` is only evaluated if the level is enabled.
+macro_rules! impl_call_site_input {
+    (
+        impl[$($generics:tt)*] for $ty:ty, $inner:tt,
+        |$input:ident, $level:ident| $admit:expr $(, sample_rate = $sample_rate:expr)?
+    ) => {
+        impl<$($generics)*, E> ErrLog<E> for $ty
+        where
+            I: ErrLog<E>,
+        {
+            #[inline]
+            #[track_caller]
+            fn error(self, error: &E) {
+                self.__log_extra(Level::Error, error, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn warn(self, error: &E) {
+                self.__log_extra(Level::Warn, error, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn info(self, error: &E) {
+                self.__log_extra(Level::Info, error, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn debug(self, error: &E) {
+                self.__log_extra(Level::Debug, error, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn trace(self, error: &E) {
+                self.__log_extra(Level::Trace, error, __Extra::default())
+            }
+            #[track_caller]
+            fn fatal(self, error: &E) -> ! {
+                self.$inner.fatal(error)
+            }
+            #[track_caller]
+            fn __expect(self, level: Level, error: &E) -> ! {
+                self.$inner.__expect(level, error)
+            }
+            #[inline]
+            #[track_caller]
+            fn __log_extra(self, level: Level, error: &E, extra: __Extra<'_>) {
+                let ($input, $level) = (&self, level);
+                if level.enabled() {
+                    if let Some(own) = $admit {
+                        let own = own.map(|own| __Noted(own, extra.note));
+                        $(let extra = extra.sampled($sample_rate);)?
+                        let extra = __Extra {
+                            note: own.as_ref().map(|own| own as &dyn Display).or(extra.note),
+                            ..extra
+                        };
+                        self.$inner.__log_extra(level, error, extra)
+                    }
+                }
+            }
+        }
+
+        impl<$($generics)*> NoneLog for $ty
+        where
+            I: NoneLog,
+        {
+            #[inline]
+            #[track_caller]
+            fn error(self) {
+                self.__log_none(Level::Error, None, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn warn(self) {
+                self.__log_none(Level::Warn, None, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn info(self) {
+                self.__log_none(Level::Info, None, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn debug(self) {
+                self.__log_none(Level::Debug, None, __Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn trace(self) {
+                self.__log_none(Level::Trace, None, __Extra::default())
+            }
+            #[track_caller]
+            fn fatal(self) -> ! {
+                self.$inner.fatal()
+            }
+            #[inline]
+            #[track_caller]
+            fn __log_none(
+                self,
+                level: Level,
+                type_name: Option<&'static str>,
+                extra: __Extra<'_>,
+            ) {
+                let ($input, $level) = (&self, level);
+                if level.enabled() {
+                    if let Some(own) = $admit {
+                        let own = own.map(|own| __Noted(own, extra.note));
+                        $(let extra = extra.sampled($sample_rate);)?
+                        let extra = __Extra {
+                            note: own.as_ref().map(|own| own as &dyn Display).or(extra.note),
+                            ..extra
+                        };
+                        self.$inner.__log_none(level, type_name, extra)
+                    }
+                }
+            }
+            #[track_caller]
+            fn __fatal_none(self, type_name: Option<&'static str>) -> ! {
+                self.$inner.__fatal_none(type_name)
+            }
+            #[track_caller]
+            fn __expect_none(self, level: Level, type_name: Option<&'static str>) -> ! {
+                self.$inner.__expect_none(level, type_name)
+            }
+        }
+    };
+}
+
+/// An [`ErrLog`] and [`NoneLog`] input that only logs `input` the first time its call site is
+/// reached with the level enabled, until [`reset_call_sites`] is called. For warnings that would
+/// otherwise repeat on every request, e.g. after a misconfiguration.
+///
+/// Up to 128 call sites are tracked by the inputs in this module, further ones always log.
+///
+/// ```rust
+/// use err_trail::{ErrContext, Once};
+///
+/// let result: Result<(), String> = Err("no cache directory configured".to_owned());
+/// let _ = result.warn(Once(()));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Once<I>(pub I);
+
+impl_call_site_input!(
+    impl[I] for Once<I>, 0,
+    |input, level| CallSite::at(Location::caller(), Kind::Once).map_or(Some(None), CallSite::once)
+);
+
+/// An [`ErrLog`] and [`NoneLog`] input that logs `input` at most [`Throttled::max`] times, or
+/// once, per window of `per` at its call site. The first event of the next window notes how many
+/// were suppressed, e.g. "(suppressed 532 similar messages)". Needs the `std` feature or a
+/// [`Clock`](crate::Clock) set with [`set_clock`](crate::set_clock), otherwise nothing is throttled
+/// and a warning is logged once. See [`Once`] for how call sites are tracked.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use err_trail::{ErrContext, Throttled};
+///
+/// let result: Result<(), String> = Err("upstream unavailable".to_owned());
+/// let _ = result.error(Throttled::new((), Duration::from_secs(10)).max(5));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Throttled<I> {
+    input: I,
+    per: Duration,
+    max: usize,
+}
+
+impl<I> Throttled<I> {
+    /// Logs `input` at most once per `per`.
+    pub const fn new(input: I, per: Duration) -> Self {
+        Throttled { input, per, max: 1 }
+    }
+
+    /// Logs at most `max` times per window instead.
+    pub const fn max(mut self, max: usize) -> Self {
+        self.max = max;
+        self
+    }
+}
+
+impl_call_site_input!(
+    impl[I] for Throttled<I>, input,
+    |input, level| match CallSite::at(Location::caller(), Kind::Throttled) {
+        // Not in a closure, so `throttle` gets the caller's location.
+        Some(call_site) => call_site.throttle(input.per, input.max),
+        None => Some(None),
+    }
+);
+
+/// An [`ErrLog`] and [`NoneLog`] input that logs `input` for the first of every `n` events at its
+/// call site, for very hot paths. Each event it logs has a `sample_rate` field of `1 / n`, so
+/// counts can be scaled back up. See [`Once`] for how call sites are tracked.
+///
+/// ```rust
+/// use err_trail::{EveryN, NoneContext};
+///
+/// let cached: Option<u32> = None;
+/// let _ = cached.trace(EveryN::new("cache miss", 100));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EveryN<I> {
+    input: I,
+    n: usize,
+}
+
+impl<I> EveryN<I> {
+    /// Logs `input` for the first of every `n` events. An `n` of 0 is treated as 1.
+    pub const fn new(input: I, n: usize) -> Self {
+        EveryN { input, n }
+    }
+}
+
+impl_call_site_input!(
+    impl[I] for EveryN<I>, input,
+    |input, level| CallSite::at(Location::caller(), Kind::EveryN)
+        .map_or(Some(None), |call_site| call_site.every_n(input.n)),
+    sample_rate = 1.0 / input.n.max(1) as f64
+);
+
+/// An [`ErrLog`] and [`NoneLog`] input that logs `input` with a chance of `rate`, from 0.0 to 1.0,
+/// for very hot paths. Each event it logs has a `sample_rate` field of `rate`, so counts can be
+/// scaled back up. See [`seed_sampling`](crate::seed_sampling) for deterministic tests.
+///
+/// ```rust
+/// use err_trail::{ErrContext, Sampled};
+///
+/// let result: Result<(), String> = Err("checksum mismatch".to_owned());
+/// let _ = result.debug(Sampled::new((), 0.01));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Sampled<I> {
+    input: I,
+    rate: f64,
+}
+
+impl<I> Sampled<I> {
+    /// Logs `input` with a chance of `rate`.
+    pub const fn new(input: I, rate: f64) -> Self {
+        Sampled { input, rate }
+    }
+}
+
+impl_call_site_input!(
+    impl[I] for Sampled<I>, input,
+    |input, level| crate::sample::sample(input.rate).then_some(None::<Suppressed>),
+    sample_rate = input.rate
+);
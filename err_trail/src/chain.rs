@@ -0,0 +1,157 @@
+use core::error::Error;
+use core::fmt::{self, Display};
+
+#[cfg(doc)]
+use crate::ErrLog;
+
+/// An [`ErrLog`] input that logs the error followed by its
+/// [`source`](core::error::Error::source) chain, e.g. "outer: middle: root".
+///
+/// Use [`Chain::separator`], [`Chain::max_depth`] or [`Chain::caused_by`] to change how the chain
+/// is rendered, or [`chain`] to render it inside a closure.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chain;
+
+impl Chain {
+    /// Separates each error in the chain with `separator` instead of ": ".
+    pub const fn separator(separator: &'static str) -> ChainFormat {
+        ChainFormat::new().separator(separator)
+    }
+
+    /// Follows at most `max_depth` sources, marking a truncated chain with "...".
+    pub const fn max_depth(max_depth: usize) -> ChainFormat {
+        ChainFormat::new().max_depth(max_depth)
+    }
+
+    /// Renders each source on its own numbered line under "Caused by:".
+    pub const fn caused_by() -> ChainFormat {
+        ChainFormat::new().caused_by()
+    }
+}
+
+/// How an error's [`source`](core::error::Error::source) chain is rendered. Also an [`ErrLog`]
+/// input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainFormat {
+    separator: &'static str,
+    max_depth: Option<usize>,
+    caused_by: bool,
+}
+
+impl ChainFormat {
+    /// The format used by [`Chain`], e.g. "outer: middle: root".
+    pub const fn new() -> Self {
+        ChainFormat {
+            separator: ": ",
+            max_depth: None,
+            caused_by: false,
+        }
+    }
+
+    /// Separates each error in the chain with `separator`. Ignored by [`ChainFormat::caused_by`].
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Follows at most `max_depth` sources, marking a truncated chain with "...".
+    pub const fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Renders each source on its own numbered line under "Caused by:".
+    pub const fn caused_by(mut self) -> Self {
+        self.caused_by = true;
+        self
+    }
+
+    /// Renders `error` and its sources in this format.
+    pub fn display<'a>(self, error: &'a (dyn Error + 'a)) -> DisplayChain<'a> {
+        DisplayChain {
+            error,
+            format: self,
+        }
+    }
+}
+
+impl Default for ChainFormat {
+    fn default() -> Self {
+        ChainFormat::new()
+    }
+}
+
+impl From<Chain> for ChainFormat {
+    fn from(_: Chain) -> Self {
+        ChainFormat::new()
+    }
+}
+
+/// Renders `error` and its [`source`](core::error::Error::source) chain as "outer: middle: root",
+/// for use inside closure inputs.
+///
+/// ```rust
+/// use err_trail::{ErrContext, chain};
+///
+/// let result: Result<(), std::io::Error> = Err(std::io::Error::other("disk full"));
+/// let _ = result.error(|e: &std::io::Error| format!("failed to save: {}", chain(e)));
+/// ```
+pub fn chain<'a>(error: &'a (dyn Error + 'a)) -> DisplayChain<'a> {
+    ChainFormat::new().display(error)
+}
+
+/// Displays an error and its [`source`](core::error::Error::source) chain. Created by [`chain`]
+/// and [`ChainFormat::display`].
+#[derive(Clone, Copy)]
+pub struct DisplayChain<'a> {
+    error: &'a (dyn Error + 'a),
+    format: ChainFormat,
+}
+
+impl fmt::Debug for DisplayChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for DisplayChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let mut source = self.error.source();
+        let mut depth = 0;
+        while let Some(error) = source {
+            if self.format.caused_by && depth == 0 {
+                f.write_str("\n\nCaused by:")?;
+            }
+            if self
+                .format
+                .max_depth
+                .is_some_and(|max_depth| depth >= max_depth)
+            {
+                return if self.format.caused_by {
+                    f.write_str("\n    ...")
+                } else {
+                    write!(f, "{}...", self.format.separator)
+                };
+            }
+            if self.format.caused_by {
+                write!(f, "\n    {}: {}", depth, error)?;
+            } else {
+                write!(f, "{}{}", self.format.separator, error)?;
+            }
+            source = error.source();
+            depth += 1;
+        }
+        Ok(())
+    }
+}
+
+impl_err_log!(
+    impl[E: Error] ErrLog<E> for Chain,
+    |input, error| chain(error)
+);
+
+impl_err_log!(
+    impl[E: Error] ErrLog<E> for ChainFormat,
+    |input, error| input.display(error)
+);
@@ -0,0 +1,135 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::time::Duration;
+
+/// A monotonic clock for throttling, e.g. [`Throttled`](crate::Throttled) and `error_throttled!`.
+///
+/// With the `std` feature, [`std::time::Instant`] is used unless another clock is set with
+/// [`set_clock`]. Without it, a clock must be set for throttling to take effect, otherwise every
+/// event is logged, with a warning the first time.
+pub trait Clock: Sync {
+    /// The time since an arbitrary fixed point. Must never decrease.
+    fn now(&self) -> Duration;
+}
+
+const UNSET: u8 = 0;
+const SETTING: u8 = 1;
+const SET: u8 = 2;
+
+/// The clock set by [`set_clock`]. Written once while `state` is `SETTING`, then only read.
+struct ClockCell {
+    state: AtomicU8,
+    clock: UnsafeCell<Option<&'static dyn Clock>>,
+}
+
+// SAFETY: `clock` is only written once, before `state` is set to `SET` with release ordering, and
+// only read after `state` is observed as `SET` with acquire ordering.
+unsafe impl Sync for ClockCell {}
+
+static CLOCK: ClockCell = ClockCell {
+    state: AtomicU8::new(UNSET),
+    clock: UnsafeCell::new(None),
+};
+
+/// Sets the clock used for throttling. This can only be done once, like `log::set_logger`.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use err_trail::Clock;
+///
+/// struct Ticks;
+///
+/// impl Clock for Ticks {
+///     fn now(&self) -> Duration {
+///         // e.g. read a hardware timer
+///         Duration::from_millis(0)
+///     }
+/// }
+///
+/// err_trail::set_clock(&Ticks).unwrap();
+/// ```
+pub fn set_clock(clock: &'static dyn Clock) -> Result<(), SetClockError> {
+    match CLOCK
+        .state
+        .compare_exchange(UNSET, SETTING, Ordering::Acquire, Ordering::Relaxed)
+    {
+        Ok(_) => {
+            // SAFETY: only the caller that moved `state` from `UNSET` to `SETTING` gets here, and
+            // no one reads `clock` until `state` is `SET`.
+            unsafe { *CLOCK.clock.get() = Some(clock) };
+            CLOCK.state.store(SET, Ordering::Release);
+            Ok(())
+        }
+        Err(_) => Err(SetClockError(())),
+    }
+}
+
+/// The error returned by [`set_clock`] if a clock was already set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetClockError(());
+
+impl fmt::Display for SetClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a clock was already set")
+    }
+}
+
+impl core::error::Error for SetClockError {}
+
+/// The current time of the set clock, or [`std::time::Instant`] with the `std` feature.
+pub(crate) fn now() -> Option<Duration> {
+    if CLOCK.state.load(Ordering::Acquire) == SET {
+        // SAFETY: `state` is `SET`, so `clock` will not be written again.
+        return unsafe { *CLOCK.clock.get() }.map(|clock| clock.now());
+    }
+    #[cfg(feature = "std")]
+    {
+        static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        Some(START.get_or_init(std::time::Instant::now).elapsed())
+    }
+    #[cfg(not(feature = "std"))]
+    None
+}
+
+/// Whether [`now_at`] warned that there is no clock.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Like [`now`], but warns the first time there is no clock, at the call site that needed one.
+pub(crate) fn now_at(location: &'static Location<'static>) -> Option<Duration> {
+    let now = now();
+    if now.is_none() && !WARNED.swap(true, Ordering::Relaxed) {
+        log_at!(
+            warn,
+            WARN,
+            Warn,
+            location,
+            None,
+            None,
+            "no clock is set, so nothing is throttled and failures escalate regardless of their window; enable the `std` feature or call `set_clock`"
+        );
+    }
+    now
+}
+
+/// Parses a duration such as "10s" at compile time for the `_throttled` macros. The units are
+/// `ms`, `s`, `m` and `h`.
+pub const fn duration(duration: &str) -> Duration {
+    let bytes = duration.as_bytes();
+    let mut value: u64 = 0;
+    let mut i = 0;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        value = value * 10 + (bytes[i] - b'0') as u64;
+        i += 1;
+    }
+    assert!(i > 0, "expected a duration such as `10s`");
+    let unit = bytes.split_at(i).1;
+    match unit {
+        b"ms" => Duration::from_millis(value),
+        b"s" => Duration::from_secs(value),
+        b"m" => Duration::from_secs(value * 60),
+        b"h" => Duration::from_secs(value * 60 * 60),
+        _ => panic!("expected a duration unit of `ms`, `s`, `m` or `h`"),
+    }
+}
@@ -0,0 +1,242 @@
+use core::fmt::{self, Display};
+
+#[cfg(doc)]
+use crate::ErrLog;
+
+/// An error with stable codes, e.g. "E1042", that support can look up in a runbook.
+///
+/// With the `derive` feature, this can be derived with a `#[code("E1042")]` attribute on a struct
+/// or on every enum variant. Doc comments become the descriptions in [`ErrorCode::CODES`].
+///
+/// ```rust
+/// # #[cfg(feature = "derive")] {
+/// use err_trail::ErrorCode;
+///
+/// #[derive(ErrorCode)]
+/// enum FetchError {
+///     /// The upstream did not answer in time.
+///     #[code("E1042")]
+///     TimedOut,
+///     #[code("E1043")]
+///     Corrupt { reason: String },
+/// }
+///
+/// assert_eq!(FetchError::TimedOut.code(), "E1042");
+/// assert_eq!(FetchError::CODES[0].description, "The upstream did not answer in time.");
+/// # }
+/// ```
+pub trait ErrorCode {
+    /// Every code of this type, for a [`Catalog`].
+    const CODES: &'static [CodeInfo];
+
+    /// The code of this error.
+    fn code(&self) -> &'static str;
+}
+
+impl<T: ErrorCode + ?Sized> ErrorCode for &T {
+    const CODES: &'static [CodeInfo] = T::CODES;
+
+    fn code(&self) -> &'static str {
+        (**self).code()
+    }
+}
+
+/// A code in [`ErrorCode::CODES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CodeInfo {
+    /// The code, e.g. "E1042".
+    pub code: &'static str,
+    /// The type or variant the code belongs to, e.g. "FetchError::TimedOut".
+    pub name: &'static str,
+    /// The doc comment of the type or variant, or "" if there is none.
+    pub description: &'static str,
+}
+
+/// An [`ErrLog`] input that logs the error prefixed with its code, e.g. "\[E1042\] timed out".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Code;
+
+impl_err_log!(
+    impl[E: ErrorCode + Display] ErrLog<E> for Code,
+    |input, error| format_args!("[{}] {}", error.code(), error)
+);
+
+/// A catalog of the codes of several [`ErrorCode`] types, rendered as JSON or Markdown. For
+/// example from a test or a small binary that writes it next to the runbook. With the `catalog`
+/// feature, [`Catalog::all`] has the codes of every type that derives [`ErrorCode`].
+///
+/// ```rust
+/// use err_trail::{Catalog, CodeInfo};
+///
+/// const CODES: &[CodeInfo] = &[CodeInfo {
+///     code: "E1042",
+///     name: "FetchError::TimedOut",
+///     description: "The upstream did not answer in time.",
+/// }];
+///
+/// let catalog = Catalog::new(&[CODES]);
+/// assert_eq!(
+///     catalog.json().to_string(),
+///     r#"[{"code":"E1042","name":"FetchError::TimedOut","description":"The upstream did not answer in time."}]"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Catalog<'a> {
+    codes: &'a [&'a [CodeInfo]],
+    /// Whether to list the codes by code, since the order of `__CODES` depends on the linker.
+    sorted: bool,
+}
+
+impl<'a> Catalog<'a> {
+    /// A catalog of `codes`, usually a list of [`ErrorCode::CODES`], in the given order.
+    pub const fn new(codes: &'a [&'a [CodeInfo]]) -> Self {
+        Catalog {
+            codes,
+            sorted: false,
+        }
+    }
+
+    /// Renders the catalog as a JSON array of objects with "code", "name" and "description".
+    pub const fn json(self) -> DisplayCatalog<'a> {
+        DisplayCatalog {
+            catalog: self,
+            markdown: false,
+        }
+    }
+
+    /// Renders the catalog as a Markdown table.
+    pub const fn markdown(self) -> DisplayCatalog<'a> {
+        DisplayCatalog {
+            catalog: self,
+            markdown: true,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'a CodeInfo> {
+        let codes = self.codes;
+        let sorted = self.sorted;
+        let mut unsorted = codes.iter().flat_map(|codes| codes.iter());
+        // Sorts without allocating, by repeatedly taking the smallest code after the last one.
+        let mut last: Option<&'a CodeInfo> = None;
+        core::iter::from_fn(move || {
+            if !sorted {
+                return unsorted.next();
+            }
+            let key = |info: &CodeInfo| (info.code, info.name);
+            last = codes
+                .iter()
+                .flat_map(|codes| codes.iter())
+                .filter(|info| last.is_none_or(|last| key(info) > key(last)))
+                .min_by_key(|info| key(info));
+            last
+        })
+    }
+}
+
+#[cfg(feature = "catalog")]
+impl Catalog<'static> {
+    /// A catalog of the codes of every type in the program that derives [`ErrorCode`], sorted by
+    /// code. The codes are collected by the linker, so a crate is only included if it is linked
+    /// into the program, e.g. with `extern crate my_crate;` in the binary that writes the catalog.
+    /// Generic types are not included.
+    ///
+    /// ```rust
+    /// use err_trail::{Catalog, ErrorCode};
+    ///
+    /// /// The upstream did not answer in time.
+    /// #[derive(ErrorCode)]
+    /// #[code("E1042")]
+    /// struct TimedOut;
+    ///
+    /// assert!(Catalog::all().markdown().to_string().contains("| E1042 | TimedOut |"));
+    /// ```
+    pub fn all() -> Self {
+        Catalog {
+            codes: __CODES.static_slice(),
+            sorted: true,
+        }
+    }
+}
+
+/// The codes of every type that derives [`ErrorCode`], registered by the derive.
+#[cfg(feature = "catalog")]
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static __CODES: [&'static [CodeInfo]];
+
+/// Displays a [`Catalog`]. Created by [`Catalog::json`] and [`Catalog::markdown`].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayCatalog<'a> {
+    catalog: Catalog<'a>,
+    markdown: bool,
+}
+
+impl Display for DisplayCatalog<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.markdown {
+            f.write_str("| Code | Name | Description |\n| --- | --- | --- |\n")?;
+            for info in self.catalog.iter() {
+                writeln!(
+                    f,
+                    "| {} | {} | {} |",
+                    Markdown(info.code),
+                    Markdown(info.name),
+                    Markdown(info.description)
+                )?;
+            }
+            Ok(())
+        } else {
+            f.write_str("[")?;
+            for (i, info) in self.catalog.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",")?;
+                }
+                write!(
+                    f,
+                    r#"{{"code":{},"name":{},"description":{}}}"#,
+                    Json(info.code),
+                    Json(info.name),
+                    Json(info.description)
+                )?;
+            }
+            f.write_str("]")
+        }
+    }
+}
+
+/// Writes a JSON string literal.
+struct Json<'a>(&'a str);
+
+impl Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+/// Writes text for a Markdown table cell.
+struct Markdown<'a>(&'a str);
+
+impl Display for Markdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '|' => f.write_str("\\|")?,
+                '\n' => f.write_str(" ")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use core::fmt::{self, Display};
+
+#[cfg(doc)]
+use crate::ErrLog;
+
+/// An [`ErrLog`] input that logs a context message together with the error, e.g.
+/// "failed to load config: file not found". Created by [`context`].
+///
+/// Use [`Context::template`] to change the separator or the order.
+#[derive(Debug, Clone, Copy)]
+pub struct Context<C> {
+    context: C,
+    template: &'static str,
+}
+
+impl<C> Context<C> {
+    /// Renders with `template` instead of "{context}: {error}". "{context}" and "{error}" are
+    /// replaced by the context and the error, any other text is kept as is, e.g.
+    /// "{error} (while {context})".
+    pub fn template(mut self, template: &'static str) -> Self {
+        self.template = template;
+        self
+    }
+}
+
+/// Logs `context` followed by the error as "{context}: {error}". Unlike passing `context` alone,
+/// the error is not discarded.
+///
+/// ```rust
+/// use err_trail::{ErrContext, context};
+///
+/// let result: Result<(), std::io::Error> = Err(std::io::Error::other("file not found"));
+/// // Logs "failed to load config: file not found"
+/// let _ = result.error(context("failed to load config"));
+/// ```
+pub fn context<C: Display>(context: C) -> Context<C> {
+    Context {
+        context,
+        template: "{context}: {error}",
+    }
+}
+
+/// Renders a [`Context`] template for one error.
+struct Rendered<'a, C, E: ?Sized> {
+    context: &'a C,
+    error: &'a E,
+    template: &'static str,
+}
+
+impl<C: Display, E: Display + ?Sized> Display for Rendered<'_, C, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.template;
+        while let Some(start) = rest.find('{') {
+            f.write_str(&rest[..start])?;
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("{context}") {
+                write!(f, "{}", self.context)?;
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("{error}") {
+                write!(f, "{}", self.error)?;
+                rest = after;
+            } else {
+                f.write_str("{")?;
+                rest = &rest[1..];
+            }
+        }
+        f.write_str(rest)
+    }
+}
+
+impl_err_log!(
+    impl[C: Display, E: Display] ErrLog<E> for Context<C>,
+    |input, error| Rendered {
+        context: &input.context,
+        error,
+        template: input.template,
+    }
+);
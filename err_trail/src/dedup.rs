@@ -0,0 +1,189 @@
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::panic::Location;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+
+use crate::Level;
+use crate::call_site::{Kind, hash, overflowed};
+
+/// Whether [`set_dedup`] turned deduplication on.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// How many call sites are deduplicated. Further call sites log every message, with a warning the
+/// first time.
+const CAPACITY: usize = 128;
+
+/// The last message of each deduplicated call site, keyed by a hash of its location.
+static SLOTS: [Slot; CAPACITY] = [const { Slot::new() }; CAPACITY];
+
+/// Whether [`SLOTS`] was full, so the warning is only logged once.
+static OVERFLOWED: AtomicBool = AtomicBool::new(false);
+
+struct Slot {
+    key: AtomicUsize,
+    location: AtomicPtr<Location<'static>>,
+    /// A hash of the level and message of the last event logged.
+    message: AtomicUsize,
+    level: AtomicU8,
+    /// The target of the last event logged, for the report of its repeats. Only accessed by
+    /// whoever set `target_locked`, which is never waited on.
+    target: UnsafeCell<&'static str>,
+    target_locked: AtomicBool,
+    repeats: AtomicUsize,
+}
+
+// SAFETY: `target` is only accessed by whoever set `target_locked`.
+unsafe impl Sync for Slot {}
+
+impl Slot {
+    const fn new() -> Self {
+        Slot {
+            key: AtomicUsize::new(0),
+            location: AtomicPtr::new(ptr::null_mut()),
+            message: AtomicUsize::new(0),
+            level: AtomicU8::new(0),
+            target: UnsafeCell::new("err_trail"),
+            target_locked: AtomicBool::new(false),
+            repeats: AtomicUsize::new(0),
+        }
+    }
+
+    /// The slot of the call site at `location`, or [`None`] if too many call sites are tracked.
+    fn at(location: &'static Location<'static>) -> Option<&'static Slot> {
+        let key = hash(location, Kind::Dedup);
+        let start = key % CAPACITY;
+        for i in 0..CAPACITY {
+            let slot = &SLOTS[(start + i) % CAPACITY];
+            match slot
+                .key
+                .compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    let location = location as *const Location<'static> as *mut _;
+                    slot.location.store(location, Ordering::Release);
+                    return Some(slot);
+                }
+                Err(existing) if existing == key => return Some(slot),
+                Err(_) => {}
+            }
+        }
+        overflowed(&OVERFLOWED, CAPACITY, location, Kind::Dedup);
+        None
+    }
+
+    /// Calls `f` with the target, or returns [`None`] if another thread is using it.
+    fn with_target<R>(&self, f: impl FnOnce(&mut &'static str) -> R) -> Option<R> {
+        self.target_locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // SAFETY: `target_locked` was set above.
+        let result = f(unsafe { &mut *self.target.get() });
+        self.target_locked.store(false, Ordering::Release);
+        Some(result)
+    }
+
+    /// Logs "last message repeated N times" if the last message was suppressed.
+    fn report(&self) {
+        let repeats = self.repeats.swap(0, Ordering::Relaxed);
+        let location = self.location.load(Ordering::Acquire);
+        if repeats == 0 || location.is_null() {
+            return;
+        }
+        // SAFETY: only ever set from a `&'static Location<'static>` in `Slot::at`.
+        let location: &'static Location<'static> = unsafe { &*location };
+        let target = self.with_target(|target| *target).unwrap_or("err_trail");
+        match Level::ALL[self.level.load(Ordering::Relaxed) as usize] {
+            Level::Error => log_at!(@repeated error, Error, location, target, repeats),
+            Level::Warn => log_at!(@repeated warn, Warn, location, target, repeats),
+            Level::Info => log_at!(@repeated info, Info, location, target, repeats),
+            Level::Debug => log_at!(@repeated debug, Debug, location, target, repeats),
+            Level::Trace => log_at!(@repeated trace, Trace, location, target, repeats),
+        }
+    }
+}
+
+/// Turns deduplication of consecutive identical messages on or off for the whole process. While
+/// on, a message that is the same as the last one logged at its call site, at the same level, is
+/// not logged. Once a different message arrives there, or on [`flush_dedup`], "last message
+/// repeated N times" is logged instead, at the level, location and target of the repeated message.
+/// For retry loops that would otherwise log the same error thousands of times in a row.
+///
+/// `tracing` needs constant targets, so there the report has the target `err_trail`, with the
+/// target of the repeated message in a `log.target` field.
+///
+/// This applies to the macros and to [`ErrContext`](crate::ErrContext) and
+/// [`NoneContext`](crate::NoneContext). Up to 128 call sites are deduplicated, further ones log
+/// every message. Turning it off flushes. While on, each message is formatted once more to compare
+/// it, so the `Display` and `Debug` implementations of its arguments run twice.
+///
+/// ```rust
+/// use err_trail::{ErrContext, flush_dedup, set_dedup};
+///
+/// set_dedup(true);
+/// for _ in 0..1000 {
+///     let _ = std::fs::read("missing.toml").warn(());
+/// }
+/// // Logs the error once, then "last message repeated 999 times"
+/// flush_dedup();
+/// ```
+pub fn set_dedup(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        flush_dedup();
+    }
+}
+
+/// Logs "last message repeated N times" for every call site whose last message was suppressed by
+/// [`set_dedup`], e.g. periodically or before exiting. Later repeats of the same message are still
+/// suppressed and counted again.
+pub fn flush_dedup() {
+    for slot in &SLOTS {
+        slot.report();
+    }
+}
+
+/// Whether to log a message at `location` with `target`, reporting the repeats of the previous
+/// message first if it differs. Always `true` unless [`set_dedup`] turned deduplication on.
+///
+/// The message is compared by a hash of it, so it is formatted here once more than by the
+/// backends, running the `Display` and `Debug` implementations of its arguments again.
+#[doc(hidden)]
+pub fn admit(
+    level: Level,
+    location: &'static Location<'static>,
+    target: &'static str,
+    message: &fmt::Arguments<'_>,
+) -> bool {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return true;
+    }
+    let Some(slot) = Slot::at(location) else {
+        return true;
+    };
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    let _ = write!(hasher, "{}{}", level, message);
+    let message = (hasher.0 as usize).max(1);
+    if slot.message.swap(message, Ordering::Relaxed) == message {
+        slot.repeats.fetch_add(1, Ordering::Relaxed);
+        return false;
+    }
+    slot.report();
+    slot.level.store(level as u8, Ordering::Relaxed);
+    // If another thread is reporting, the repeats of this message are reported with the target
+    // of the previous one.
+    slot.with_target(|slot_target| *slot_target = target);
+    true
+}
+
+/// Hashes a message with FNV-1a as it is formatted, without storing it.
+struct Fnv(u64);
+
+impl Write for Fnv {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        Ok(())
+    }
+}
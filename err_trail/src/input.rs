@@ -0,0 +1,64 @@
+use core::fmt::{self, Debug, Display};
+
+#[cfg(doc)]
+use crate::{ErrLog, NoneLog};
+
+/// An [`ErrLog`] input that logs the error with its [`Debug`] implementation, `{:?}`. For errors
+/// that do not implement [`Display`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dbg;
+
+/// An [`ErrLog`] input that logs the error with its pretty [`Debug`] implementation, `{:#?}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrettyDbg;
+
+/// An [`ErrLog`] input that logs the error with its alternate [`Display`] implementation, `{:#}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Alt;
+
+/// An [`ErrLog`] and [`NoneLog`] input that logs any [`Display`] value, for types without their
+/// own implementation, e.g. `result.error(Msg(path.display()))`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Msg<T>(pub T);
+
+/// Formats a value with `{:?}` or `{:#?}` through [`Display`], so it can be logged without
+/// allocating.
+struct DebugAsDisplay<'a, T: ?Sized> {
+    value: &'a T,
+    pretty: bool,
+}
+
+impl<T: Debug + ?Sized> Display for DebugAsDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pretty {
+            write!(f, "{:#?}", self.value)
+        } else {
+            write!(f, "{:?}", self.value)
+        }
+    }
+}
+
+impl_err_log!(
+    impl[E: Debug] ErrLog<E> for Dbg,
+    |input, error| DebugAsDisplay { value: error, pretty: false }
+);
+
+impl_err_log!(
+    impl[E: Debug] ErrLog<E> for PrettyDbg,
+    |input, error| DebugAsDisplay { value: error, pretty: true }
+);
+
+impl_err_log!(
+    impl[E: Display] ErrLog<E> for Alt,
+    |input, error| format_args!("{:#}", error)
+);
+
+impl_err_log!(
+    impl[T: Display, E] ErrLog<E> for Msg<T>,
+    |input, error| input.0
+);
+
+impl_none_log!(
+    impl[T: Display] NoneLog for Msg<T>,
+    |input| input.0
+);
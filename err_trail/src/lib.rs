@@ -3,378 +3,2907 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+// The exported macros expand in the caller's crate, where `#[cfg(feature = ..)]` would check the
+// caller's features rather than these. Each backend has a gate macro defined here once per `cfg`,
+// which keeps its body only when the backend is enabled for this crate, and the backends are
+// reached through the re-exports below. `defmt` macros expand to `defmt::` paths, so with `defmt`
+// the caller depends on it directly.
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
+
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log as __log_crate;
+
+/// Whether `defmt` is enabled for this crate.
+#[doc(hidden)]
+pub const __DEFMT: bool = cfg!(feature = "defmt");
+
+/// Keeps its body only with the `tracing` feature.
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
 #[macro_export]
-macro_rules! error {
-    ($($arg:tt)*) => {{
+macro_rules! __if_tracing {
+    ($($body:tt)*) => {
+        $($body)*
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_tracing {
+    ($($body:tt)*) => {};
+}
+
+/// Keeps its body only with the `log` feature.
+#[cfg(feature = "log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_log {
+    ($($body:tt)*) => {
+        $($body)*
+    };
+}
+
+#[cfg(not(feature = "log"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_log {
+    ($($body:tt)*) => {};
+}
+
+/// Keeps its body only with the `defmt` feature.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_defmt {
+    ($($body:tt)*) => {
+        $($body)*
+    };
+}
+
+#[cfg(not(feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_defmt {
+    ($($body:tt)*) => {};
+}
+
+/// Whether any enabled backend will record an event at the given level and target, so message
+/// arguments are only evaluated when needed. `defmt` filters at compile time, so it always reports
+/// enabled here.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enabled {
+    (target: $target:expr, $tracing_level:ident, $log_level:ident) => {{
+        #[allow(unused_mut)]
+        let mut enabled = $crate::__DEFMT;
+        $crate::__if_tracing! {
+            enabled = enabled
+                || $crate::__tracing::enabled!(target: $target, $crate::__tracing::Level::$tracing_level);
+        }
+        $crate::__if_log! {
+            enabled = enabled
+                || $crate::__log_crate::log_enabled!(target: $target, $crate::__log_crate::Level::$log_level);
+        }
+        enabled
+    }};
+}
+
+/// Shared implementation of the level macros. Leading `target:`, `name:` and `parent:` metadata is
+/// collected as `[target] [name] [parent]`, then leading `key = value` fields, terminated by `;`,
+/// as `(key sigil value)` before the event is emitted.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    (@meta $level:tt [] $name:tt $parent:tt target: $target:expr, $($rest:tt)+) => {
+        $crate::__log!(@meta $level [$target] $name $parent $($rest)+)
+    };
+    (@meta $level:tt $target:tt [] $parent:tt name: $name:expr, $($rest:tt)+) => {
+        $crate::__log!(@meta $level $target [$name] $parent $($rest)+)
+    };
+    (@meta $level:tt $target:tt $name:tt [] parent: $parent:expr, $($rest:tt)+) => {
+        $crate::__log!(@meta $level $target $name [$parent] $($rest)+)
+    };
+    (@meta $level:tt $target:tt $name:tt $parent:tt $key:ident = $($rest:tt)+) => {
+        $crate::__log!(@fields $level ($target $name $parent) [] $key = $($rest)+)
+    };
+    (@meta $level:tt $target:tt $name:tt $parent:tt $($arg:tt)+) => {
+        $crate::__log!(@emit $level ($target $name $parent) [] $($arg)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = % $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $level $meta [$($fields)* ($key % $value)] $($rest)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = % $value:expr; $($rest:tt)+) => {
+        $crate::__log!(@emit $level $meta [$($fields)* ($key % $value)] $($rest)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = ? $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $level $meta [$($fields)* ($key ? $value)] $($rest)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = ? $value:expr; $($rest:tt)+) => {
+        $crate::__log!(@emit $level $meta [$($fields)* ($key ? $value)] $($rest)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = $value:expr, $($rest:tt)+) => {
+        $crate::__log!(@fields $level $meta [$($fields)* ($key = $value)] $($rest)+)
+    };
+    (@fields $level:tt $meta:tt [$($fields:tt)*] $key:ident = $value:expr; $($rest:tt)+) => {
+        $crate::__log!(@emit $level $meta [$($fields)* ($key = $value)] $($rest)+)
+    };
+    (@emit ($level:ident, $tracing_level:ident, $log_level:ident, fatal) $meta:tt $fields:tt $($arg:tt)+) => {{
+        let args = format_args!($($arg)+);
+        if $crate::__log!(@enabled ($tracing_level, $log_level) $meta) {
+            $crate::__log!(@backends ($level, $log_level) $meta $fields args);
+        }
+        $crate::__fatal(args)
+    }};
+    (@emit ($level:ident, $tracing_level:ident, $log_level:ident, when $note:expr, sample_rate = $rate:expr)
+        $meta:tt [$($fields:tt)*] $($arg:tt)+) => {{
+        // Like `when`, with the chance of the event being logged attached as a `sample_rate` field.
+        if $crate::__log!(@enabled ($tracing_level, $log_level) $meta) {
+            if let Some(note) = $note {
+                let note = note.as_ref().map(|note| note as &dyn core::fmt::Display);
+                #[allow(unused_variables)]
+                let args = format_args!("{}", $crate::__Noted(format_args!($($arg)+), note));
+                let rate: f64 = $rate;
+                if $crate::__log!(@admit $log_level $meta args) {
+                    $crate::__log!(@backends ($level, $log_level) $meta [$($fields)* (sample_rate = rate)] args);
+                }
+            }
+        }
+    }};
+    (@emit ($level:ident, $tracing_level:ident, $log_level:ident, when $note:expr) $meta:tt $fields:tt $($arg:tt)+) => {{
+        // `$note` is only evaluated if enabled. It decides whether to log, with an optional note
+        // displayed after the message.
+        if $crate::__log!(@enabled ($tracing_level, $log_level) $meta) {
+            if let Some(note) = $note {
+                let note = note.as_ref().map(|note| note as &dyn core::fmt::Display);
+                #[allow(unused_variables)]
+                let args = format_args!("{}", $crate::__Noted(format_args!($($arg)+), note));
+                if $crate::__log!(@admit $log_level $meta args) {
+                    $crate::__log!(@backends ($level, $log_level) $meta $fields args);
+                }
+            }
+        }
+    }};
+    (@emit ($level:ident, $tracing_level:ident, $log_level:ident) $meta:tt $fields:tt $($arg:tt)+) => {{
+        if $crate::__log!(@enabled ($tracing_level, $log_level) $meta) {
+            #[allow(unused_variables)]
+            let args = format_args!($($arg)+);
+            if $crate::__log!(@admit $log_level $meta args) {
+                $crate::__log!(@backends ($level, $log_level) $meta $fields args);
+            }
+        }
+    }};
+    (@admit $log_level:ident ([$($target:expr)?] $name:tt $parent:tt) $args:ident) => {
+        // In a `#[track_caller]` function this is the caller's location, so repeats are then
+        // deduplicated per caller.
+        $crate::__dedup(
+            $crate::Level::$log_level,
+            core::panic::Location::caller(),
+            $crate::__or!([$($target)?] module_path!()),
+            &$args,
+        )
+    };
+    (@enabled ($tracing_level:ident, $log_level:ident) ([$($target:expr)?] $name:tt $parent:tt)) => {
+        $crate::__enabled!(
+            target: $crate::__or!([$($target)?] module_path!()),
+            $tracing_level,
+            $log_level
+        )
+    };
+    (@backends ($level:ident, $log_level:ident)
+        ([$($target:expr)?] [$($name:expr)?] [$($parent:expr)?])
+        [] $args:ident) => {
+        $crate::__if_tracing! {
+            $crate::__tracing::$level!(
+                name: $crate::__or!([$($name)?] concat!("event ", file!(), ":", line!())),
+                target: $crate::__or!([$($target)?] module_path!()),
+                $(parent: $parent,)?
+                "{}",
+                $args
+            );
+        }
+        $crate::__if_log! {
+            $crate::__log_crate::$level!(
+                target: $crate::__or!([$($target)?] module_path!()),
+                "{}{}",
+                $crate::__scope(),
+                $args
+            );
+        }
+        $crate::__if_defmt! {{
+            $(let $args = format_args!("[{}] {}", $target, $args);)?
+            defmt::$level!(
+                "{}{}",
+                defmt::Display2Format(&$crate::__scope()),
+                defmt::Display2Format(&$args)
+            );
+        }}
+    };
+    (@backends ($level:ident, $log_level:ident)
+        ([$($target:expr)?] [$($name:expr)?] [$($parent:expr)?])
+        [$(($key:ident $sigil:tt $value:expr))+] $args:ident) => {
+        // Each value is evaluated once, after the message, and shared by every backend.
         #[allow(unused_variables)]
-        let args = format_args!($($arg)*);
+        let ($($key,)+) = ($(&$value,)+);
+        $crate::__if_tracing! {
+            $crate::__tracing::$level!(
+                name: $crate::__or!([$($name)?] concat!("event ", file!(), ":", line!())),
+                target: $crate::__or!([$($target)?] module_path!()),
+                $(parent: $parent,)?
+                $($key = $crate::__field!(tracing $sigil $key),)+
+                "{}",
+                $args
+            );
+        }
+        $crate::__if_log! {
+            $crate::__log_crate::$level!(
+                target: $crate::__or!([$($target)?] module_path!()),
+                $($key = $crate::__field!(log $sigil $key)),+;
+                "{}{}",
+                $crate::__scope(),
+                $args
+            );
+        }
+        $crate::__if_defmt! {{
+            $(let $args = format_args!("[{}] {}", $target, $args);)?
+            defmt::$level!(
+                "{}{}{}",
+                defmt::Display2Format(&$crate::__scope()),
+                defmt::Display2Format(&$args),
+                defmt::Display2Format(&format_args!(
+                    concat!($(" ", stringify!($key), "=", $crate::__field!(spec $sigil)),+),
+                    $($key),+
+                ))
+            );
+        }}
+    };
+    ($level:tt; $($arg:tt)+) => {
+        $crate::__log!(@meta $level [] [] [] $($arg)+)
+    };
+}
+
+/// Logs at a [`Level`] only known at runtime, accepting everything the level macros do.
+///
+/// ```rust
+/// use err_trail::{Level, log};
+///
+/// let level: Level = "warn".parse().unwrap();
+/// log!(level, "disk usage at {}%", 91);
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        match $level {
+            $crate::Level::Error => $crate::__log!((error, ERROR, Error); $($arg)+),
+            $crate::Level::Warn => $crate::__log!((warn, WARN, Warn); $($arg)+),
+            $crate::Level::Info => $crate::__log!((info, INFO, Info); $($arg)+),
+            $crate::Level::Debug => $crate::__log!((debug, DEBUG, Debug); $($arg)+),
+            $crate::Level::Trace => $crate::__log!((trace, TRACE, Trace); $($arg)+),
+        }
+    };
+}
+
+/// The given value if present, otherwise the default.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __or {
+    ([] $default:expr) => {
+        $default
+    };
+    ([$value:expr] $default:expr) => {
+        $value
+    };
+}
+
+/// Converts a bound field into the value each backend expects. `=` records the value as is, `%`
+/// through `Display` and `?` through `Debug`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field {
+    (tracing = $value:ident) => {
+        $value
+    };
+    (tracing % $value:ident) => {
+        $crate::__tracing::field::display($value)
+    };
+    (tracing ? $value:ident) => {
+        $crate::__tracing::field::debug($value)
+    };
+    (log = $value:ident) => {
+        $value
+    };
+    (log % $value:ident) => {
+        $crate::__log_crate::kv::Value::from_display($value)
+    };
+    (log ? $value:ident) => {
+        $crate::__log_crate::kv::Value::from_debug($value)
+    };
+    (spec =) => {
+        "{}"
+    };
+    (spec %) => {
+        "{}"
+    };
+    (spec ?) => {
+        "{:?}"
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::__log!((error, ERROR, Error); $($arg)+)
+    };
+}
+
+/// Logs as "error" on every enabled backend, then panics with the same message. With the
+/// `fatal-abort` feature the process aborts instead of unwinding. Accepts everything the level
+/// macros do.
+///
+/// ```rust,should_panic
+/// use err_trail::fatal;
+///
+/// fatal!("configuration is corrupt: {}", "missing key");
+/// ```
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => {
+        $crate::__log!((error, ERROR, Error, fatal); $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::__log!((warn, WARN, Warn); $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::__log!((info, INFO, Info); $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::__log!((debug, DEBUG, Debug); $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::__log!((trace, TRACE, Trace); $($arg)+)
+    };
+}
+
+/// Logs the error of a [`Result::Err`], or that an [`Option`] is [`None`], at a level, then
+/// evaluates to the value unchanged. An optional context message is logged before the error, e.g.
+/// "failed to load config: file not found".
+///
+/// Unlike [`ErrContext`] and [`NoneContext`], the event is logged by the level macros at the call
+/// site, so its target is the caller's module path, and its file and line are the caller's for
+/// every backend, e.g. for filtering by target per crate.
+///
+/// ```rust
+/// use err_trail::trail;
+///
+/// fn load(path: &str) -> Result<String, std::io::Error> {
+///     let config = trail!(warn, std::fs::read_to_string(path), "failed to load {}", path)?;
+///     Ok(config)
+/// }
+///
+/// let port: Option<u16> = trail!(error, "http".parse::<u16>().ok());
+/// ```
+#[macro_export]
+macro_rules! trail {
+    ($level:ident, $value:expr $(,)?) => {
+        match $value {
+            value => {
+                if let Some(failure) = $crate::__Fallible::__failure(&value) {
+                    $crate::$level!("{}", failure);
+                }
+                value
+            }
+        }
+    };
+    ($level:ident, $value:expr, $($context:tt)+) => {
+        match $value {
+            value => {
+                if let Some(failure) = $crate::__Fallible::__failure(&value) {
+                    $crate::$level!("{}{}", format_args!($($context)+), failure.after_context());
+                }
+                value
+            }
+        }
+    };
+}
+
+/// Shared implementation of the `_throttled` macros. Each expansion has its own call site state.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __throttled {
+    (($level:ident, $tracing_level:ident, $log_level:ident); per = $per:tt, max = $max:expr, $($arg:tt)+) => {{
+        const PER: core::time::Duration = $crate::__duration(stringify!($per));
+        static CALL_SITE: $crate::__CallSite = $crate::__CallSite::new();
+        $crate::__log!(($level, $tracing_level, $log_level, when CALL_SITE.throttle(PER, $max)); $($arg)+)
+    }};
+    ($level:tt; per = $per:tt, $($arg:tt)+) => {
+        $crate::__throttled!($level; per = $per, max = 1, $($arg)+)
+    };
+}
+
+/// Like [`error!`], but logs at most `max` times, or once, per window of `per` at this call site.
+/// The first event of the next window notes how many were suppressed. `per` is a number with a
+/// unit of `ms`, `s`, `m` or `h`, e.g. `error_throttled!(per = 10s, max = 5, "...")`. Needs the
+/// `std` feature or a [`Clock`] set with [`set_clock`], otherwise nothing is throttled and a warning
+/// is logged once.
+#[macro_export]
+macro_rules! error_throttled {
+    ($($arg:tt)+) => {
+        $crate::__throttled!((error, ERROR, Error); $($arg)+)
+    };
+}
+
+/// Like [`warn!`], but logs at most `max` times, or once, per window of `per` at this call site.
+/// The first event of the next window notes how many were suppressed. `per` is a number with a
+/// unit of `ms`, `s`, `m` or `h`, e.g. `warn_throttled!(per = 10s, max = 5, "...")`. Needs the
+/// `std` feature or a [`Clock`] set with [`set_clock`], otherwise nothing is throttled and a warning
+/// is logged once.
+#[macro_export]
+macro_rules! warn_throttled {
+    ($($arg:tt)+) => {
+        $crate::__throttled!((warn, WARN, Warn); $($arg)+)
+    };
+}
+
+/// Like [`info!`], but logs at most `max` times, or once, per window of `per` at this call site.
+/// The first event of the next window notes how many were suppressed. `per` is a number with a
+/// unit of `ms`, `s`, `m` or `h`, e.g. `info_throttled!(per = 10s, max = 5, "...")`. Needs the
+/// `std` feature or a [`Clock`] set with [`set_clock`], otherwise nothing is throttled and a warning
+/// is logged once.
+#[macro_export]
+macro_rules! info_throttled {
+    ($($arg:tt)+) => {
+        $crate::__throttled!((info, INFO, Info); $($arg)+)
+    };
+}
+
+/// Like [`debug!`], but logs at most `max` times, or once, per window of `per` at this call site.
+/// The first event of the next window notes how many were suppressed. `per` is a number with a
+/// unit of `ms`, `s`, `m` or `h`, e.g. `debug_throttled!(per = 10s, max = 5, "...")`. Needs the
+/// `std` feature or a [`Clock`] set with [`set_clock`], otherwise nothing is throttled and a warning
+/// is logged once.
+#[macro_export]
+macro_rules! debug_throttled {
+    ($($arg:tt)+) => {
+        $crate::__throttled!((debug, DEBUG, Debug); $($arg)+)
+    };
+}
+
+/// Like [`trace!`], but logs at most `max` times, or once, per window of `per` at this call site.
+/// The first event of the next window notes how many were suppressed. `per` is a number with a
+/// unit of `ms`, `s`, `m` or `h`, e.g. `trace_throttled!(per = 10s, max = 5, "...")`. Needs the
+/// `std` feature or a [`Clock`] set with [`set_clock`], otherwise nothing is throttled and a warning
+/// is logged once.
+#[macro_export]
+macro_rules! trace_throttled {
+    ($($arg:tt)+) => {
+        $crate::__throttled!((trace, TRACE, Trace); $($arg)+)
+    };
+}
+
+/// Shared implementation of the `_once` macros. Each expansion has its own call site state.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __once {
+    (($level:ident, $tracing_level:ident, $log_level:ident); $($arg:tt)+) => {{
+        static CALL_SITE: $crate::__CallSite = $crate::__CallSite::new();
+        $crate::__log!(($level, $tracing_level, $log_level, when CALL_SITE.once()); $($arg)+)
+    }};
+}
+
+/// Like [`error!`], but only logs the first time this call site is reached with "error" enabled,
+/// until [`reset_call_sites`] is called.
+#[macro_export]
+macro_rules! error_once {
+    ($($arg:tt)+) => {
+        $crate::__once!((error, ERROR, Error); $($arg)+)
+    };
+}
+
+/// Like [`warn!`], but only logs the first time this call site is reached with "warn" enabled,
+/// until [`reset_call_sites`] is called.
+#[macro_export]
+macro_rules! warn_once {
+    ($($arg:tt)+) => {
+        $crate::__once!((warn, WARN, Warn); $($arg)+)
+    };
+}
+
+/// Like [`info!`], but only logs the first time this call site is reached with "info" enabled,
+/// until [`reset_call_sites`] is called.
+#[macro_export]
+macro_rules! info_once {
+    ($($arg:tt)+) => {
+        $crate::__once!((info, INFO, Info); $($arg)+)
+    };
+}
+
+/// Like [`debug!`], but only logs the first time this call site is reached with "debug" enabled,
+/// until [`reset_call_sites`] is called.
+#[macro_export]
+macro_rules! debug_once {
+    ($($arg:tt)+) => {
+        $crate::__once!((debug, DEBUG, Debug); $($arg)+)
+    };
+}
+
+/// Like [`trace!`], but only logs the first time this call site is reached with "trace" enabled,
+/// until [`reset_call_sites`] is called.
+#[macro_export]
+macro_rules! trace_once {
+    ($($arg:tt)+) => {
+        $crate::__once!((trace, TRACE, Trace); $($arg)+)
+    };
+}
+
+/// Shared implementation of the `_every_n` macros. Each expansion has its own call site state.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __every_n {
+    (($level:ident, $tracing_level:ident, $log_level:ident); $n:expr, $($arg:tt)+) => {{
+        static CALL_SITE: $crate::__CallSite = $crate::__CallSite::new();
+        let n: usize = $n;
+        let rate = 1.0 / n.max(1) as f64;
+        $crate::__log!(($level, $tracing_level, $log_level, when CALL_SITE.every_n(n), sample_rate = rate); $($arg)+)
+    }};
+}
+
+/// Like [`error!`], but only logs the first of every `n` events at this call site, with a
+/// `sample_rate` field of `1 / n`, e.g. `error_every_n!(100, "...")`. See [`reset_call_sites`].
+#[macro_export]
+macro_rules! error_every_n {
+    ($($arg:tt)+) => {
+        $crate::__every_n!((error, ERROR, Error); $($arg)+)
+    };
+}
+
+/// Like [`warn!`], but only logs the first of every `n` events at this call site, with a
+/// `sample_rate` field of `1 / n`, e.g. `warn_every_n!(100, "...")`. See [`reset_call_sites`].
+#[macro_export]
+macro_rules! warn_every_n {
+    ($($arg:tt)+) => {
+        $crate::__every_n!((warn, WARN, Warn); $($arg)+)
+    };
+}
+
+/// Like [`info!`], but only logs the first of every `n` events at this call site, with a
+/// `sample_rate` field of `1 / n`, e.g. `info_every_n!(100, "...")`. See [`reset_call_sites`].
+#[macro_export]
+macro_rules! info_every_n {
+    ($($arg:tt)+) => {
+        $crate::__every_n!((info, INFO, Info); $($arg)+)
+    };
+}
+
+/// Like [`debug!`], but only logs the first of every `n` events at this call site, with a
+/// `sample_rate` field of `1 / n`, e.g. `debug_every_n!(100, "...")`. See [`reset_call_sites`].
+#[macro_export]
+macro_rules! debug_every_n {
+    ($($arg:tt)+) => {
+        $crate::__every_n!((debug, DEBUG, Debug); $($arg)+)
+    };
+}
+
+/// Like [`trace!`], but only logs the first of every `n` events at this call site, with a
+/// `sample_rate` field of `1 / n`, e.g. `trace_every_n!(100, "...")`. See [`reset_call_sites`].
+#[macro_export]
+macro_rules! trace_every_n {
+    ($($arg:tt)+) => {
+        $crate::__every_n!((trace, TRACE, Trace); $($arg)+)
+    };
+}
+
+/// Shared implementation of the `_sampled` macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sampled {
+    (($level:ident, $tracing_level:ident, $log_level:ident); $rate:expr, $($arg:tt)+) => {{
+        let rate: f64 = $rate;
+        $crate::__log!(($level, $tracing_level, $log_level, when $crate::__sample(rate).then_some(None::<&str>), sample_rate = rate); $($arg)+)
+    }};
+}
+
+/// Like [`error!`], but only logs with a chance of `rate`, from 0.0 to 1.0, with a `sample_rate`
+/// field of `rate`, e.g. `error_sampled!(0.01, "...")`. See [`seed_sampling`].
+#[macro_export]
+macro_rules! error_sampled {
+    ($($arg:tt)+) => {
+        $crate::__sampled!((error, ERROR, Error); $($arg)+)
+    };
+}
+
+/// Like [`warn!`], but only logs with a chance of `rate`, from 0.0 to 1.0, with a `sample_rate`
+/// field of `rate`, e.g. `warn_sampled!(0.01, "...")`. See [`seed_sampling`].
+#[macro_export]
+macro_rules! warn_sampled {
+    ($($arg:tt)+) => {
+        $crate::__sampled!((warn, WARN, Warn); $($arg)+)
+    };
+}
+
+/// Like [`info!`], but only logs with a chance of `rate`, from 0.0 to 1.0, with a `sample_rate`
+/// field of `rate`, e.g. `info_sampled!(0.01, "...")`. See [`seed_sampling`].
+#[macro_export]
+macro_rules! info_sampled {
+    ($($arg:tt)+) => {
+        $crate::__sampled!((info, INFO, Info); $($arg)+)
+    };
+}
+
+/// Like [`debug!`], but only logs with a chance of `rate`, from 0.0 to 1.0, with a `sample_rate`
+/// field of `rate`, e.g. `debug_sampled!(0.01, "...")`. See [`seed_sampling`].
+#[macro_export]
+macro_rules! debug_sampled {
+    ($($arg:tt)+) => {
+        $crate::__sampled!((debug, DEBUG, Debug); $($arg)+)
+    };
+}
+
+/// Like [`trace!`], but only logs with a chance of `rate`, from 0.0 to 1.0, with a `sample_rate`
+/// field of `rate`, e.g. `trace_sampled!(0.01, "...")`. See [`seed_sampling`].
+#[macro_export]
+macro_rules! trace_sampled {
+    ($($arg:tt)+) => {
+        $crate::__sampled!((trace, TRACE, Trace); $($arg)+)
+    };
+}
+
+/// Creates a [`Span`] at a [`Level`], with a name and optional `key = value` fields, which accept
+/// the `%` and `?` sigils like the level macros. Call [`Span::enter`] or [`Span::in_scope`] for
+/// events to happen inside it. With `tracing` it is a `tracing` span. With `log` and `defmt`, the
+/// name and fields are prefixed onto the events inside it instead.
+///
+/// ```rust
+/// use err_trail::{Level, info, span};
+///
+/// let span = span!(Level::Info, "request", id = 7, path = %"/health");
+/// span.in_scope(|| {
+///     // With `log`, logs "request{id=7 path=/health}: handled"
+///     info!("handled");
+/// });
+/// ```
+#[macro_export]
+macro_rules! span {
+    ($level:expr, $name:expr $(, $($fields:tt)*)?) => {
+        $crate::__span!(@fields ($level, $name) [] $($($fields)*)?)
+    };
+}
+
+/// Shared implementation of [`span!`]. Fields are collected as `(key sigil value)`, then the span
+/// is created for each backend.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __span {
+    (@fields $meta:tt [$($fields:tt)*] $key:ident = % $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__span!(@fields $meta [$($fields)* ($key % $value)] $($($rest)*)?)
+    };
+    (@fields $meta:tt [$($fields:tt)*] $key:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__span!(@fields $meta [$($fields)* ($key ? $value)] $($($rest)*)?)
+    };
+    (@fields $meta:tt [$($fields:tt)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__span!(@fields $meta [$($fields)* ($key = $value)] $($($rest)*)?)
+    };
+    (@fields ($level:expr, $name:expr) $fields:tt) => {
+        match $level {
+            $crate::Level::Error => $crate::__span!(@new (ERROR, Error) $name, $fields),
+            $crate::Level::Warn => $crate::__span!(@new (WARN, Warn) $name, $fields),
+            $crate::Level::Info => $crate::__span!(@new (INFO, Info) $name, $fields),
+            $crate::Level::Debug => $crate::__span!(@new (DEBUG, Debug) $name, $fields),
+            $crate::Level::Trace => $crate::__span!(@new (TRACE, Trace) $name, $fields),
+        }
+    };
+    (@new ($tracing_level:ident, $log_level:ident) $name:expr, [$(($key:ident $sigil:tt $value:expr))*]) => {
+        if $crate::__enabled!(target: module_path!(), $tracing_level, $log_level) {
+            // Each value is evaluated once and shared by every backend.
+            #[allow(unused_variables)]
+            let ($($key,)*) = ($(&$value,)*);
+            #[allow(unused_mut)]
+            let mut span = $crate::Span::__new(
+                $name,
+                format_args!(
+                    concat!($(" ", stringify!($key), "=", $crate::__field!(spec $sigil)),*),
+                    $($key),*
+                ),
+            );
+            $crate::__if_tracing! {
+                span = span.__tracing($crate::__tracing::span!(
+                    $crate::__tracing::Level::$tracing_level,
+                    $name,
+                    $($key = $crate::__field!(tracing $sigil $key)),*
+                ));
+            }
+            span
+        } else {
+            $crate::Span::none()
+        }
+    };
+}
+
+use core::cell::{Cell, OnceCell};
+use core::fmt::{self, Display};
+use core::panic::Location;
+use core::time::Duration;
+
+/// Logs on every enabled backend, attributing the event to `$location` (the original caller)
+/// rather than to this crate. `$location` is forwarded as file and line metadata, since
+/// [`Location`] does not carry the caller's module path. The target is always "err_trail",
+/// whichever module the input is implemented in. [`trail!`] logs at the call site instead.
+macro_rules! log_at {
+    (
+        @backends $level:ident,
+        $log_level:ident,
+        $location:ident,
+        $type_name:ident,
+        $sample_rate:ident,
+        $args:ident
+    ) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(
+            target: "err_trail",
+            {
+                code.filepath = $location.file(),
+                code.lineno = $location.line(),
+                error.type = $type_name,
+                sample_rate = $sample_rate,
+            },
+            "{}",
+            $args
+        );
+        #[cfg(feature = "log")]
+        {
+            let level = log::Level::$log_level;
+            if level <= log::STATIC_MAX_LEVEL && level <= log::max_level() {
+                let key_values = [
+                    $type_name.map(|type_name| ("error.type", log::kv::Value::from(type_name))),
+                    $sample_rate.map(|rate| ("sample_rate", log::kv::Value::from(rate))),
+                ];
+                log::logger().log(
+                    &log::Record::builder()
+                        .args(format_args!("{}{}", crate::__scope(), $args))
+                        .level(level)
+                        .target("err_trail")
+                        .file_static(Some($location.file()))
+                        .line(Some($location.line()))
+                        .key_values(&key_values)
+                        .build(),
+                );
+            }
+        }
+        #[cfg(feature = "defmt")]
+        defmt::$level!(
+            "{}{}{} ({=str}:{=u32})",
+            defmt::Display2Format(&crate::__scope()),
+            defmt::Display2Format(&$args),
+            defmt::Display2Format(&crate::Fields {
+                type_name: $type_name,
+                sample_rate: $sample_rate,
+            }),
+            $location.file(),
+            $location.line()
+        );
+    };
+    (
+        $level:ident,
+        $tracing_level:ident,
+        $log_level:ident,
+        $location:expr,
+        $type_name:expr,
+        $sample_rate:expr,
+        $($arg:tt)*
+    ) => {{
+        let location: &'static core::panic::Location<'static> = $location;
+        if crate::__enabled!(target: "err_trail", $tracing_level, $log_level) {
+            let type_name: Option<&'static str> = $type_name;
+            let sample_rate: Option<f64> = $sample_rate;
+            let args = format_args!($($arg)*);
+            if crate::dedup::admit(crate::Level::$log_level, location, "err_trail", &args) {
+                log_at!(@backends $level, $log_level, location, type_name, sample_rate, args);
+            }
+        }
+    }};
+    // Reports the repeats of a message suppressed by `set_dedup`, bypassing deduplication, with the
+    // target of the repeated message. `tracing` needs a constant target, so there it is a field.
+    (@repeated $level:ident, $log_level:ident, $location:ident, $target:ident, $repeats:ident) => {{
+        let args = format_args!("last message repeated {} times", $repeats);
         #[cfg(feature = "tracing")]
-        tracing::error!("{}", args);
+        tracing::$level!(
+            target: "err_trail",
+            {
+                code.filepath = $location.file(),
+                code.lineno = $location.line(),
+                log.target = $target,
+            },
+            "{}",
+            args
+        );
         #[cfg(feature = "log")]
-        log::error!("{}", args);
+        {
+            let level = log::Level::$log_level;
+            if level <= log::STATIC_MAX_LEVEL && level <= log::max_level() {
+                log::logger().log(
+                    &log::Record::builder()
+                        .args(format_args!("{}{}", crate::__scope(), args))
+                        .level(level)
+                        .target($target)
+                        .file_static(Some($location.file()))
+                        .line(Some($location.line()))
+                        .build(),
+                );
+            }
+        }
         #[cfg(feature = "defmt")]
-        defmt::error!("{}", defmt::Display2Format(&args));
+        defmt::$level!(
+            "{}{} ({=str}:{=u32})",
+            defmt::Display2Format(&crate::__scope()),
+            defmt::Display2Format(&args),
+            $location.file(),
+            $location.line()
+        );
     }};
 }
 
-#[macro_export]
-macro_rules! warn {
-    ($($arg:tt)*) => {{
-        #[allow(unused_variables)]
-        let args = format_args!($($arg)*);
-        #[cfg(feature = "tracing")]
-        tracing::warn!("{}", args);
-        #[cfg(feature = "log")]
-        log::warn!("{}", args);
-        #[cfg(feature = "defmt")]
-        defmt::warn!("{}", defmt::Display2Format(&args));
-    }};
-}
+/// Like [`log_at`] as "error", but always produces the message, then passes it to [`__fatal`].
+macro_rules! fatal_at {
+    ($location:expr, $type_name:expr, $($arg:tt)*) => {{
+        let location: &'static core::panic::Location<'static> = $location;
+        let args = format_args!($($arg)*);
+        if crate::__enabled!(target: "err_trail", ERROR, Error) {
+            let type_name: Option<&'static str> = $type_name;
+            let sample_rate: Option<f64> = None;
+            log_at!(@backends error, Error, location, type_name, sample_rate, args);
+        }
+        crate::__fatal(args)
+    }};
+}
+
+/// Like [`log_at`], at a [`Level`] only known at runtime.
+macro_rules! log_level_at {
+    ($level:expr, $location:expr, $type_name:expr, $sample_rate:expr, $($arg:tt)*) => {
+        match $level {
+            crate::Level::Error => {
+                log_at!(error, ERROR, Error, $location, $type_name, $sample_rate, $($arg)*)
+            }
+            crate::Level::Warn => {
+                log_at!(warn, WARN, Warn, $location, $type_name, $sample_rate, $($arg)*)
+            }
+            crate::Level::Info => {
+                log_at!(info, INFO, Info, $location, $type_name, $sample_rate, $($arg)*)
+            }
+            crate::Level::Debug => {
+                log_at!(debug, DEBUG, Debug, $location, $type_name, $sample_rate, $($arg)*)
+            }
+            crate::Level::Trace => {
+                log_at!(trace, TRACE, Trace, $location, $type_name, $sample_rate, $($arg)*)
+            }
+        }
+    };
+}
+
+/// The name of `$ty` to attach to an event as `error.type` with the `type-name` feature, otherwise
+/// [`None`].
+macro_rules! type_name_of {
+    ($ty:ty) => {
+        if cfg!(feature = "type-name") {
+            Some(core::any::type_name::<$ty>())
+        } else {
+            None
+        }
+    };
+}
+
+/// Implements [`ErrLog`] for an input whose message is produced by `$message` from the input and
+/// the error, only when the event is enabled.
+macro_rules! impl_err_log {
+    (
+        impl[$($generics:tt)*] ErrLog<$e:ty> for $ty:ty $(where [$($bounds:tt)*])?,
+        |$input:ident, $error:ident| $message:expr
+    ) => {
+        impl<$($generics)*> crate::ErrLog<$e> for $ty $(where $($bounds)*)? {
+            #[inline]
+            #[track_caller]
+            fn error(self, error: &$e) {
+                self.__log_extra(crate::Level::Error, error, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn warn(self, error: &$e) {
+                self.__log_extra(crate::Level::Warn, error, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn info(self, error: &$e) {
+                self.__log_extra(crate::Level::Info, error, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn debug(self, error: &$e) {
+                self.__log_extra(crate::Level::Debug, error, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn trace(self, error: &$e) {
+                self.__log_extra(crate::Level::Trace, error, crate::__Extra::default())
+            }
+            #[track_caller]
+            fn fatal(self, error: &$e) -> ! {
+                let ($input, $error) = (self, error);
+                fatal_at!(core::panic::Location::caller(), type_name_of!($e), "{}", $message)
+            }
+            #[track_caller]
+            fn __expect(self, level: crate::Level, error: &$e) -> ! {
+                let ($input, $error) = (self, error);
+                let message = $message;
+                log_level_at!(
+                    level,
+                    core::panic::Location::caller(),
+                    type_name_of!($e),
+                    None,
+                    "{}",
+                    message
+                );
+                panic!("{}", message)
+            }
+            #[inline]
+            #[track_caller]
+            fn __log_extra(self, level: crate::Level, error: &$e, extra: crate::__Extra<'_>) {
+                let ($input, $error) = (self, error);
+                log_level_at!(
+                    level,
+                    core::panic::Location::caller(),
+                    type_name_of!($e),
+                    extra.sample_rate,
+                    "{}",
+                    crate::__Noted($message, extra.note)
+                )
+            }
+        }
+    };
+}
+
+/// Implements [`NoneLog`] for an input whose message is produced by `$message` from the input, and
+/// optionally the type name of the missing value, only when the event is enabled.
+macro_rules! impl_none_log {
+    (
+        impl[$($generics:tt)*] NoneLog for $ty:ty $(where [$($bounds:tt)*])?,
+        |$input:ident $(, $type_name:ident)?| $message:expr
+    ) => {
+        impl<$($generics)*> crate::NoneLog for $ty $(where $($bounds)*)? {
+            #[inline]
+            #[track_caller]
+            fn error(self) {
+                self.__log_none(crate::Level::Error, None, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn warn(self) {
+                self.__log_none(crate::Level::Warn, None, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn info(self) {
+                self.__log_none(crate::Level::Info, None, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn debug(self) {
+                self.__log_none(crate::Level::Debug, None, crate::__Extra::default())
+            }
+            #[inline]
+            #[track_caller]
+            fn trace(self) {
+                self.__log_none(crate::Level::Trace, None, crate::__Extra::default())
+            }
+            #[track_caller]
+            fn fatal(self) -> ! {
+                self.__fatal_none(None)
+            }
+            #[inline]
+            #[track_caller]
+            fn __log_none(
+                self,
+                level: crate::Level,
+                type_name: Option<&'static str>,
+                extra: crate::__Extra<'_>,
+            ) {
+                let $input = self;
+                $(let $type_name = type_name;)?
+                let location = core::panic::Location::caller();
+                let type_name = type_name.filter(|_| cfg!(feature = "type-name"));
+                let message = crate::__Noted($message, extra.note);
+                log_level_at!(level, location, type_name, extra.sample_rate, "{}", message)
+            }
+            #[track_caller]
+            fn __fatal_none(self, type_name: Option<&'static str>) -> ! {
+                let $input = self;
+                $(let $type_name = type_name;)?
+                let location = core::panic::Location::caller();
+                let type_name = type_name.filter(|_| cfg!(feature = "type-name"));
+                fatal_at!(location, type_name, "{}", $message)
+            }
+            #[track_caller]
+            fn __expect_none(self, level: crate::Level, type_name: Option<&'static str>) -> ! {
+                let $input = self;
+                $(let $type_name = type_name;)?
+                let location = core::panic::Location::caller();
+                let type_name = type_name.filter(|_| cfg!(feature = "type-name"));
+                let message = $message;
+                log_level_at!(level, location, type_name, None, "{}", message);
+                panic!("{}", message)
+            }
+        }
+    };
+}
+
+mod call_site;
+mod chain;
+mod clock;
+mod code;
+mod context;
+mod dedup;
+mod input;
+mod level;
+mod sample;
+mod severity;
+mod span;
+
+#[doc(hidden)]
+pub use call_site::CallSite as __CallSite;
+pub use call_site::{EveryN, Once, Sampled, Throttled, reset_call_sites};
+pub use chain::{Chain, ChainFormat, DisplayChain, chain};
+#[doc(hidden)]
+pub use clock::duration as __duration;
+pub use clock::{Clock, SetClockError, set_clock};
+#[cfg(feature = "catalog")]
+#[doc(hidden)]
+pub use code::__CODES;
+pub use code::{Catalog, Code, CodeInfo, DisplayCatalog, ErrorCode};
+pub use context::{Context, context};
+#[doc(hidden)]
+pub use dedup::admit as __dedup;
+pub use dedup::{flush_dedup, set_dedup};
+pub use input::{Alt, Dbg, Msg, PrettyDbg};
+pub use level::{Level, ParseLevelError};
+#[cfg(feature = "catalog")]
+#[doc(hidden)]
+pub use linkme as __linkme;
+#[doc(hidden)]
+pub use sample::sample as __sample;
+pub use sample::seed_sampling;
+pub use severity::Severity;
+#[doc(hidden)]
+pub use span::__scope;
+pub use span::{Entered, Span};
+
+#[cfg(feature = "derive")]
+pub use err_trail_derive::{ErrorCode, Severity, instrument};
+
+/// Ends the process after a fatal event has been logged. Panics with `args`, or aborts with the
+/// `fatal-abort` feature.
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __fatal(args: core::fmt::Arguments<'_>) -> ! {
+    #[cfg(feature = "fatal-abort")]
+    {
+        // Unwinding out of an `extern "C"` function aborts, which also works without `std`. It is
+        // only ever called from Rust, so the argument type does not need to be FFI-safe.
+        #[allow(improper_ctypes_definitions)]
+        extern "C" fn abort(args: core::fmt::Arguments<'_>) -> ! {
+            panic!("{}", args)
+        }
+        abort(args)
+    }
+    #[cfg(not(feature = "fatal-abort"))]
+    panic!("{}", args)
+}
+
+/// Calls the body of a function for `#[instrument]`. As a `FnOnce`, the body can return borrows of
+/// what it captured.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+#[inline(always)]
+pub fn __call<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// What inputs that wrap other inputs, such as [`Throttled`], add to the events they pass on.
+#[doc(hidden)]
+#[derive(Clone, Copy, Default)]
+pub struct __Extra<'a> {
+    /// Displayed after the message.
+    pub note: Option<&'a dyn Display>,
+    /// The chance of the event being logged, attached as `sample_rate`.
+    pub sample_rate: Option<f64>,
+}
+
+impl __Extra<'_> {
+    /// Also sampled with a chance of `rate`, e.g. when sampled inputs are nested.
+    pub fn sampled(self, rate: f64) -> Self {
+        __Extra {
+            sample_rate: Some(self.sample_rate.unwrap_or(1.0) * rate),
+            ..self
+        }
+    }
+}
+
+/// The fields `log_at` attaches, displayed as a suffix for `defmt`, e.g. " error.type=u32".
+#[cfg(feature = "defmt")]
+struct Fields {
+    type_name: Option<&'static str>,
+    sample_rate: Option<f64>,
+}
+
+#[cfg(feature = "defmt")]
+impl Display for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(type_name) = self.type_name {
+            write!(f, " error.type={}", type_name)?;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            write!(f, " sample_rate={}", sample_rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a message followed by an optional note, separated by a space.
+#[doc(hidden)]
+pub struct __Noted<'a, M>(pub M, pub Option<&'a dyn Display>);
+
+impl<M: Display> Display for __Noted<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(note) => write!(f, "{} {}", self.0, note),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// A [`Result`] or [`Option`] for [`trail!`].
+#[doc(hidden)]
+pub trait __Fallible {
+    /// The error, or the missing value, if failed.
+    fn __failure(&self) -> Option<__Failure<'_>>;
+}
+
+impl<T, E: Display> __Fallible for Result<T, E> {
+    #[inline]
+    fn __failure(&self) -> Option<__Failure<'_>> {
+        self.as_ref().err().map(|error| __Failure::Err(error))
+    }
+}
+
+impl<T> __Fallible for Option<T> {
+    #[inline]
+    fn __failure(&self) -> Option<__Failure<'_>> {
+        self.is_none().then(|| __Failure::None(type_name_of!(T)))
+    }
+}
+
+/// What [`trail!`] logs: the error, or that a value, of the type name if known, is missing.
+#[doc(hidden)]
+pub enum __Failure<'a> {
+    Err(&'a dyn Display),
+    None(Option<&'static str>),
+}
+
+impl<'a> __Failure<'a> {
+    /// Displayed after a context message, e.g. ": file not found". A missing value is only logged
+    /// as the context message.
+    pub fn after_context(self) -> impl Display + 'a {
+        AfterContext(match self {
+            __Failure::Err(error) => Some(error),
+            __Failure::None(_) => None,
+        })
+    }
+}
+
+/// Displays ": {error}", or nothing.
+struct AfterContext<'a>(Option<&'a dyn Display>);
+
+impl Display for AfterContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(error) => write!(f, ": {}", error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for __Failure<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            __Failure::Err(error) => error.fmt(f),
+            __Failure::None(type_name) => write!(f, "expected Some({})", type_name.unwrap_or("_")),
+        }
+    }
+}
+
+/// Defers producing a message until a backend formats it, producing it at most once.
+struct Lazy<F, D> {
+    f: Cell<Option<F>>,
+    value: OnceCell<D>,
+}
+
+impl<F, D> Lazy<F, D>
+where
+    F: FnOnce() -> D,
+{
+    #[inline]
+    fn new(f: F) -> Self {
+        Lazy {
+            f: Cell::new(Some(f)),
+            value: OnceCell::new(),
+        }
+    }
+}
+
+impl<F, D> Display for Lazy<F, D>
+where
+    F: FnOnce() -> D,
+    D: Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let value = self.value.get_or_init(|| match self.f.take() {
+            Some(produce) => produce(),
+            None => unreachable!("`Lazy` is only initialized once"),
+        });
+        value.fmt(f)
+    }
+}
+
+mod sealed {
+    /// A sealed trait to prevent external implementations.
+    pub trait Sealed {}
+}
+
+pub trait ErrLog<E> {
+    #[track_caller]
+    fn error(self, error: &E);
+    #[track_caller]
+    fn warn(self, error: &E);
+    #[track_caller]
+    fn info(self, error: &E);
+    #[track_caller]
+    fn debug(self, error: &E);
+    #[track_caller]
+    fn trace(self, error: &E);
+    /// Logs as "error", then panics, or aborts with the `fatal-abort` feature.
+    #[track_caller]
+    fn fatal(self, error: &E) -> !
+    where
+        Self: Sized,
+    {
+        self.error(error);
+        __fatal(format_args!("a fatal error was logged"))
+    }
+    #[track_caller]
+    #[inline]
+    fn log(self, level: Level, error: &E)
+    where
+        Self: Sized,
+    {
+        match level {
+            Level::Error => self.error(error),
+            Level::Warn => self.warn(error),
+            Level::Info => self.info(error),
+            Level::Debug => self.debug(error),
+            Level::Trace => self.trace(error),
+        }
+    }
+    /// Logs at `level`, then panics with the message, for [`ErrContext::expect_log`]. Inputs that
+    /// do not override this panic with a generic message.
+    #[doc(hidden)]
+    #[track_caller]
+    fn __expect(self, level: Level, error: &E) -> !
+    where
+        Self: Sized,
+    {
+        #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+        self.log(level, error);
+        panic!("called `Result::expect_log` on an `Err` value")
+    }
+    /// Logs at `level` with `extra` added to the event. Called by inputs that wrap other inputs,
+    /// such as [`Throttled`]. Inputs that do not override this log the note separately, and do not
+    /// attach the sample rate.
+    #[doc(hidden)]
+    #[track_caller]
+    fn __log_extra(self, level: Level, error: &E, extra: __Extra<'_>)
+    where
+        Self: Sized,
+    {
+        self.log(level, error);
+        if let Some(note) = extra.note {
+            log_level_at!(
+                level,
+                Location::caller(),
+                None,
+                extra.sample_rate,
+                "{}",
+                note
+            )
+        }
+    }
+}
+
+impl_err_log!(
+    impl[E: Display] ErrLog<E> for (),
+    |input, error| error
+);
+
+impl_err_log!(
+    impl[E] ErrLog<E> for &str,
+    |input, error| input
+);
+
+#[cfg(feature = "alloc")]
+impl_err_log!(
+    impl[E] ErrLog<E> for alloc::string::String,
+    |input, error| input
+);
+
+#[cfg(feature = "alloc")]
+impl_err_log!(
+    impl[E] ErrLog<E> for alloc::borrow::Cow<'_, str>,
+    |input, error| input
+);
+
+impl_err_log!(
+    impl[E] ErrLog<E> for fmt::Arguments<'_>,
+    |input, error| input
+);
+
+impl_err_log!(
+    impl[E] ErrLog<E> for &dyn Display,
+    |input, error| input
+);
+
+impl_err_log!(
+    impl[F, E, D] ErrLog<E> for F where [F: FnOnce(&E) -> D, D: Display],
+    |input, error| Lazy::new(|| input(error))
+);
+
+pub trait NoneLog {
+    #[track_caller]
+    fn error(self);
+    #[track_caller]
+    fn warn(self);
+    #[track_caller]
+    fn info(self);
+    #[track_caller]
+    fn debug(self);
+    #[track_caller]
+    fn trace(self);
+    /// Logs as "error", then panics, or aborts with the `fatal-abort` feature.
+    #[track_caller]
+    fn fatal(self) -> !
+    where
+        Self: Sized,
+    {
+        self.error();
+        __fatal(format_args!("a fatal error was logged"))
+    }
+    /// Logs at `level` with `extra` added to the event. Called by [`NoneContext`] with the type
+    /// name of the missing value, which the built-in inputs attach as `error.type` with the
+    /// `type-name` feature. Inputs that do not override this log the note separately, and do not
+    /// attach the sample rate.
+    #[doc(hidden)]
+    #[track_caller]
+    fn __log_none(self, level: Level, type_name: Option<&'static str>, extra: __Extra<'_>)
+    where
+        Self: Sized,
+    {
+        self.log(level);
+        if let Some(note) = extra.note {
+            log_level_at!(
+                level,
+                Location::caller(),
+                None,
+                extra.sample_rate,
+                "{}",
+                note
+            )
+        }
+    }
+    /// Like [`NoneLog::__log_none`], for [`NoneLog::fatal`].
+    #[doc(hidden)]
+    #[track_caller]
+    fn __fatal_none(self, type_name: Option<&'static str>) -> !
+    where
+        Self: Sized,
+    {
+        self.fatal()
+    }
+    /// Like [`NoneLog::__log_none`], then panics with the message, for
+    /// [`NoneContext::expect_log`]. Inputs that do not override this panic with a generic message.
+    #[doc(hidden)]
+    #[track_caller]
+    fn __expect_none(self, level: Level, type_name: Option<&'static str>) -> !
+    where
+        Self: Sized,
+    {
+        #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+        self.__log_none(level, type_name, __Extra::default());
+        panic!("called `Option::expect_log` on a `None` value")
+    }
+    #[track_caller]
+    #[inline]
+    fn log(self, level: Level)
+    where
+        Self: Sized,
+    {
+        match level {
+            Level::Error => self.error(),
+            Level::Warn => self.warn(),
+            Level::Info => self.info(),
+            Level::Debug => self.debug(),
+            Level::Trace => self.trace(),
+        }
+    }
+}
+
+impl_none_log!(
+    impl[] NoneLog for (),
+    |input, type_name| format_args!(
+        "expected Some({}) at {}:{}",
+        type_name.unwrap_or("_"),
+        Location::caller().file(),
+        Location::caller().line()
+    )
+);
+
+impl_none_log!(
+    impl[] NoneLog for &str,
+    |input| input
+);
+
+#[cfg(feature = "alloc")]
+impl_none_log!(
+    impl[] NoneLog for alloc::string::String,
+    |input| input
+);
+
+#[cfg(feature = "alloc")]
+impl_none_log!(
+    impl[] NoneLog for alloc::borrow::Cow<'_, str>,
+    |input| input
+);
+
+impl_none_log!(
+    impl[] NoneLog for fmt::Arguments<'_>,
+    |input| input
+);
+
+impl_none_log!(
+    impl[] NoneLog for &dyn Display,
+    |input| input
+);
+
+impl_none_log!(
+    impl[F, D] NoneLog for F where [F: FnOnce() -> D, D: Display],
+    |input| Lazy::new(input)
+);
+
+/// For logging a [`Result`] when [`Result::Err`] is encountered.
+pub trait ErrContext<T, E>: sealed::Sealed {
+    /// If [`Result::Err`], logging as "error".
+    #[track_caller]
+    fn error(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn".
+    #[track_caller]
+    fn warn(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "info".
+    #[track_caller]
+    fn info(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "debug".
+    #[track_caller]
+    fn debug(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "trace".
+    #[track_caller]
+    fn trace(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "error" and then panicking, or aborting with the
+    /// `fatal-abort` feature. Otherwise returns the contained [`Result::Ok`] value.
+    #[track_caller]
+    fn fatal(self, input: impl ErrLog<E>) -> T;
+    /// If [`Result::Err`], logging at the given level.
+    #[track_caller]
+    fn log(self, level: Level, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the level chosen by the error's [`Severity`].
+    #[track_caller]
+    fn log_auto(self, input: impl ErrLog<E>) -> Result<T, E>
+    where
+        E: Severity;
+    /// If [`Result::Err`], logging the message returned by `f` as "error". Unlike passing a closure
+    /// to [`ErrContext::error`], the error type of `f` is inferred.
+    #[track_caller]
+    fn error_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// If [`Result::Err`], logging the message returned by `f` as "warn". Unlike passing a closure
+    /// to [`ErrContext::warn`], the error type of `f` is inferred.
+    #[track_caller]
+    fn warn_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// If [`Result::Err`], logging the message returned by `f` as "info". Unlike passing a closure
+    /// to [`ErrContext::info`], the error type of `f` is inferred.
+    #[track_caller]
+    fn info_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// If [`Result::Err`], logging the message returned by `f` as "debug". Unlike passing a closure
+    /// to [`ErrContext::debug`], the error type of `f` is inferred.
+    #[track_caller]
+    fn debug_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// If [`Result::Err`], logging the message returned by `f` as "trace". Unlike passing a closure
+    /// to [`ErrContext::trace`], the error type of `f` is inferred.
+    #[track_caller]
+    fn trace_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// Like [`ErrContext::fatal`], with the error type of `f` inferred.
+    #[track_caller]
+    fn fatal_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> T;
+    /// Like [`ErrContext::log`], with the error type of `f` inferred.
+    #[track_caller]
+    fn log_with<D: Display>(self, level: Level, f: impl FnOnce(&E) -> D) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the given level. The result is consumed, for the last
+    /// handler of an error.
+    #[track_caller]
+    fn log_and_discard(self, level: Level, input: impl ErrLog<E>);
+    /// If [`Result::Err`], logging at the given level. Then converts to an [`Option`] like
+    /// [`Result::ok`].
+    #[track_caller]
+    fn ok_or_log(self, level: Level, input: impl ErrLog<E>) -> Option<T>;
+    /// If [`Result::Err`], logging at the given level and returning `default`. Otherwise returns
+    /// the contained [`Result::Ok`] value.
+    #[track_caller]
+    fn unwrap_or_log(self, default: T, level: Level, input: impl ErrLog<E>) -> T;
+    /// If [`Result::Err`], logging at the given level and returning the value computed by `f`
+    /// from the error. Otherwise returns the contained [`Result::Ok`] value.
+    #[track_caller]
+    fn unwrap_or_else_log(self, f: impl FnOnce(E) -> T, level: Level, input: impl ErrLog<E>) -> T;
+    /// If [`Result::Err`], logging at the given level and returning [`Default::default`].
+    /// Otherwise returns the contained [`Result::Ok`] value.
+    #[track_caller]
+    fn unwrap_or_default_log(self, level: Level, input: impl ErrLog<E>) -> T
+    where
+        T: Default;
+    /// If [`Result::Err`], logging at the given level and then panicking with the same message,
+    /// like [`Result::expect`]. Otherwise returns the contained [`Result::Ok`] value. Unlike
+    /// [`ErrContext::fatal`], this always panics.
+    #[track_caller]
+    fn expect_log(self, level: Level, input: impl ErrLog<E>) -> T;
+    /// If [`Result::Ok`], logging the value as "info", e.g. for audit trails. The value is passed
+    /// to `input` in place of an error.
+    #[track_caller]
+    fn info_ok(self, input: impl ErrLog<T>) -> Result<T, E>;
+    /// If [`Result::Ok`], logging the value at the given level.
+    #[track_caller]
+    fn log_ok(self, level: Level, input: impl ErrLog<T>) -> Result<T, E>;
+    /// Logs the value with `ok_input` at `ok_level` if [`Result::Ok`], or the error with
+    /// `err_input` at `err_level` if [`Result::Err`].
+    #[track_caller]
+    fn log_result(
+        self,
+        ok_level: Level,
+        err_level: Level,
+        ok_input: impl ErrLog<T>,
+        err_input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging as "error".
+    #[track_caller]
+    fn error_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging as "warn".
+    #[track_caller]
+    fn warn_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging as "info".
+    #[track_caller]
+    fn info_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging as "debug".
+    #[track_caller]
+    fn debug_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging as "trace".
+    #[track_caller]
+    fn trace_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns true, logging at the given level. `predicate` is passed
+    /// the error, and only called if the level is enabled.
+    #[track_caller]
+    fn log_if(
+        self,
+        level: Level,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging as "error".
+    #[track_caller]
+    fn error_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging as "warn".
+    #[track_caller]
+    fn warn_unless(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>)
+    -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging as "info".
+    #[track_caller]
+    fn info_unless(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>)
+    -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging as "debug".
+    #[track_caller]
+    fn debug_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging as "trace".
+    #[track_caller]
+    fn trace_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`] and `predicate` returns false, logging at the given level. `predicate` is only
+    /// called if the level is enabled.
+    #[track_caller]
+    fn log_unless(
+        self,
+        level: Level,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "error" only the first time this call site is reached.
+    #[track_caller]
+    fn error_once(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn" only the first time this call site is reached.
+    #[track_caller]
+    fn warn_once(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "info" only the first time this call site is reached.
+    #[track_caller]
+    fn info_once(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "debug" only the first time this call site is reached.
+    #[track_caller]
+    fn debug_once(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "trace" only the first time this call site is reached.
+    #[track_caller]
+    fn trace_once(self, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the given level only the first time this call site is
+    /// reached, like the [`Once`] input.
+    #[track_caller]
+    fn log_once(self, level: Level, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "error" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn error_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn warn_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "info" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn info_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "debug" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn debug_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "trace" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn trace_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the given level at most `max` times per `per` at this call
+    /// site, like the [`Throttled`] input.
+    #[track_caller]
+    fn log_throttled(
+        self,
+        level: Level,
+        per: Duration,
+        max: usize,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "error" only the first of every `n` errors at this call site.
+    #[track_caller]
+    fn error_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn" only the first of every `n` errors at this call site.
+    #[track_caller]
+    fn warn_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "info" only the first of every `n` errors at this call site.
+    #[track_caller]
+    fn info_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "debug" only the first of every `n` errors at this call site.
+    #[track_caller]
+    fn debug_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "trace" only the first of every `n` errors at this call site.
+    #[track_caller]
+    fn trace_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the given level only the first of every `n` errors at this
+    /// call site, like the [`EveryN`] input.
+    #[track_caller]
+    fn log_every_n(self, level: Level, n: usize, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "error" with a chance of `rate`.
+    #[track_caller]
+    fn error_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn" with a chance of `rate`.
+    #[track_caller]
+    fn warn_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "info" with a chance of `rate`.
+    #[track_caller]
+    fn info_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "debug" with a chance of `rate`.
+    #[track_caller]
+    fn debug_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "trace" with a chance of `rate`.
+    #[track_caller]
+    fn trace_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging at the given level with a chance of `rate`, like the [`Sampled`]
+    /// input.
+    #[track_caller]
+    fn log_sampled(self, level: Level, rate: f64, input: impl ErrLog<E>) -> Result<T, E>;
+    /// If [`Result::Err`], logging as "warn", or as "error" once `threshold` errors in a row were
+    /// seen at this call site within `window` of the first, until the next [`Result::Ok`], which
+    /// resets the count. So a single failure stays a warning, while an outage is logged as errors
+    /// for as long as it lasts. See [`Clock`] for how the window is measured. Without one, errors
+    /// are counted until a [`Result::Ok`], with a warning the first time.
+    #[track_caller]
+    fn warn_escalating(
+        self,
+        threshold: usize,
+        window: Duration,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+    /// Like [`ErrContext::warn_escalating`], logging at the given level until escalating.
+    #[track_caller]
+    fn log_escalating(
+        self,
+        level: Level,
+        threshold: usize,
+        window: Duration,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E>;
+}
+
+/// For logging a [`Option`] when [`Option::None`] is encountered.
+pub trait NoneContext<T>: sealed::Sealed {
+    /// If [`Option::None`], logging as "error".
+    #[track_caller]
+    fn error(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn".
+    #[track_caller]
+    fn warn(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "info".
+    #[track_caller]
+    fn info(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "debug".
+    #[track_caller]
+    fn debug(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "trace".
+    #[track_caller]
+    fn trace(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "error" and then panicking, or aborting with the
+    /// `fatal-abort` feature. Otherwise returns the contained [`Option::Some`] value.
+    #[track_caller]
+    fn fatal(self, input: impl NoneLog) -> T;
+    /// If [`Option::None`], logging at the given level.
+    #[track_caller]
+    fn log(self, level: Level, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging at the given level. The option is consumed, for the last
+    /// handler of a missing value.
+    #[track_caller]
+    fn log_and_discard(self, level: Level, input: impl NoneLog);
+    /// If [`Option::None`], logging at the given level and returning `default`. Otherwise returns
+    /// the contained [`Option::Some`] value.
+    #[track_caller]
+    fn unwrap_or_log(self, default: T, level: Level, input: impl NoneLog) -> T;
+    /// If [`Option::None`], logging at the given level and returning the value computed by `f`.
+    /// Otherwise returns the contained [`Option::Some`] value.
+    #[track_caller]
+    fn unwrap_or_else_log(self, f: impl FnOnce() -> T, level: Level, input: impl NoneLog) -> T;
+    /// If [`Option::None`], logging at the given level and returning [`Default::default`].
+    /// Otherwise returns the contained [`Option::Some`] value.
+    #[track_caller]
+    fn unwrap_or_default_log(self, level: Level, input: impl NoneLog) -> T
+    where
+        T: Default;
+    /// If [`Option::None`], logging at the given level and then panicking with the same message,
+    /// like [`Option::expect`]. Otherwise returns the contained [`Option::Some`] value. Unlike
+    /// [`NoneContext::fatal`], this always panics.
+    #[track_caller]
+    fn expect_log(self, level: Level, input: impl NoneLog) -> T;
+    /// If [`Option::Some`], logging the value as "warn", for when a value is unexpected. The
+    /// value is passed to `input` in place of an error.
+    #[track_caller]
+    fn warn_some(self, input: impl ErrLog<T>) -> Option<T>;
+    /// If [`Option::Some`], logging the value at the given level.
+    #[track_caller]
+    fn log_some(self, level: Level, input: impl ErrLog<T>) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging as "error".
+    #[track_caller]
+    fn error_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging as "warn".
+    #[track_caller]
+    fn warn_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging as "info".
+    #[track_caller]
+    fn info_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging as "debug".
+    #[track_caller]
+    fn debug_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging as "trace".
+    #[track_caller]
+    fn trace_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns true, logging at the given level. `predicate` is passed
+    /// nothing, so it can check captured state, and only called if the level is enabled.
+    #[track_caller]
+    fn log_if(
+        self,
+        level: Level,
+        predicate: impl FnOnce() -> bool,
+        input: impl NoneLog,
+    ) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging as "error".
+    #[track_caller]
+    fn error_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging as "warn".
+    #[track_caller]
+    fn warn_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging as "info".
+    #[track_caller]
+    fn info_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging as "debug".
+    #[track_caller]
+    fn debug_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging as "trace".
+    #[track_caller]
+    fn trace_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`] and `predicate` returns false, logging at the given level. `predicate` is only
+    /// called if the level is enabled.
+    #[track_caller]
+    fn log_unless(
+        self,
+        level: Level,
+        predicate: impl FnOnce() -> bool,
+        input: impl NoneLog,
+    ) -> Option<T>;
+    /// If [`Option::None`], logging as "error" only the first time this call site is reached.
+    #[track_caller]
+    fn error_once(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn" only the first time this call site is reached.
+    #[track_caller]
+    fn warn_once(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "info" only the first time this call site is reached.
+    #[track_caller]
+    fn info_once(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "debug" only the first time this call site is reached.
+    #[track_caller]
+    fn debug_once(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "trace" only the first time this call site is reached.
+    #[track_caller]
+    fn trace_once(self, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging at the given level only the first time this call site is
+    /// reached, like the [`Once`] input.
+    #[track_caller]
+    fn log_once(self, level: Level, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "error" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn error_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn warn_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "info" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn info_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "debug" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn debug_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "trace" at most `max` times per `per` at this call site.
+    #[track_caller]
+    fn trace_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging at the given level at most `max` times per `per` at this call
+    /// site, like the [`Throttled`] input.
+    #[track_caller]
+    fn log_throttled(
+        self,
+        level: Level,
+        per: Duration,
+        max: usize,
+        input: impl NoneLog,
+    ) -> Option<T>;
+    /// If [`Option::None`], logging as "error" only the first of every `n` [`Option::None`]s at
+    /// this call site.
+    #[track_caller]
+    fn error_every_n(self, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn" only the first of every `n` [`Option::None`]s at this
+    /// call site.
+    #[track_caller]
+    fn warn_every_n(self, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "info" only the first of every `n` [`Option::None`]s at this
+    /// call site.
+    #[track_caller]
+    fn info_every_n(self, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "debug" only the first of every `n` [`Option::None`]s at
+    /// this call site.
+    #[track_caller]
+    fn debug_every_n(self, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "trace" only the first of every `n` [`Option::None`]s at
+    /// this call site.
+    #[track_caller]
+    fn trace_every_n(self, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging at the given level only the first of every `n`
+    /// [`Option::None`]s at this call site, like the [`EveryN`] input.
+    #[track_caller]
+    fn log_every_n(self, level: Level, n: usize, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "error" with a chance of `rate`.
+    #[track_caller]
+    fn error_sampled(self, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn" with a chance of `rate`.
+    #[track_caller]
+    fn warn_sampled(self, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "info" with a chance of `rate`.
+    #[track_caller]
+    fn info_sampled(self, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "debug" with a chance of `rate`.
+    #[track_caller]
+    fn debug_sampled(self, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "trace" with a chance of `rate`.
+    #[track_caller]
+    fn trace_sampled(self, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging at the given level with a chance of `rate`, like the
+    /// [`Sampled`] input.
+    #[track_caller]
+    fn log_sampled(self, level: Level, rate: f64, input: impl NoneLog) -> Option<T>;
+    /// If [`Option::None`], logging as "warn", or as "error" once `threshold` [`Option::None`]s in
+    /// a row were seen at this call site within `window` of the first, until the next
+    /// [`Option::Some`], which resets the count. See [`Clock`] for how the window is measured.
+    /// Without one, they are counted until a [`Option::Some`], with a warning the first time.
+    #[track_caller]
+    fn warn_escalating(self, threshold: usize, window: Duration, input: impl NoneLog) -> Option<T>;
+    /// Like [`NoneContext::warn_escalating`], logging at the given level until escalating.
+    #[track_caller]
+    fn log_escalating(
+        self,
+        level: Level,
+        threshold: usize,
+        window: Duration,
+        input: impl NoneLog,
+    ) -> Option<T>;
+}
+
+impl<T, E> sealed::Sealed for Result<T, E> {}
+
+impl<T, E> ErrContext<T, E> for Result<T, E> {
+    #[inline]
+    #[track_caller]
+    fn error(self, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.error(&err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn(self, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.warn(&err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info(self, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.info(&err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug(self, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.debug(&err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace(self, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.trace(&err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn fatal(self, input: impl ErrLog<E>) -> T {
+        match self {
+            Ok(value) => value,
+            Err(err) => input.fatal(&err),
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log(self, level: Level, input: impl ErrLog<E>) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.log(level, &err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_auto(self, input: impl ErrLog<E>) -> Result<T, E>
+    where
+        E: Severity,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.log(err.level(), &err);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.error(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.warn(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.info(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.debug(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.trace(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn fatal_with<D: Display>(self, f: impl FnOnce(&E) -> D) -> T {
+        self.fatal(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_with<D: Display>(self, level: Level, f: impl FnOnce(&E) -> D) -> Result<T, E> {
+        self.log(level, f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_and_discard(self, level: Level, input: impl ErrLog<E>) {
+        let _ = self.log(level, input);
+    }
+
+    #[inline]
+    #[track_caller]
+    fn ok_or_log(self, level: Level, input: impl ErrLog<E>) -> Option<T> {
+        self.log(level, input).ok()
+    }
+
+    #[inline]
+    #[track_caller]
+    fn unwrap_or_log(self, default: T, level: Level, input: impl ErrLog<E>) -> T {
+        self.log(level, input).unwrap_or(default)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn unwrap_or_else_log(self, f: impl FnOnce(E) -> T, level: Level, input: impl ErrLog<E>) -> T {
+        self.log(level, input).unwrap_or_else(f)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn unwrap_or_default_log(self, level: Level, input: impl ErrLog<E>) -> T
+    where
+        T: Default,
+    {
+        self.log(level, input).unwrap_or_default()
+    }
+
+    #[inline]
+    #[track_caller]
+    fn expect_log(self, level: Level, input: impl ErrLog<E>) -> T {
+        match self {
+            Ok(value) => value,
+            Err(err) => input.__expect(level, &err),
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_ok(self, input: impl ErrLog<T>) -> Result<T, E> {
+        self.log_ok(Level::Info, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_ok(self, level: Level, input: impl ErrLog<T>) -> Result<T, E> {
+        match self {
+            Ok(value) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                input.log(level, &value);
+                Ok(value)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_result(
+        self,
+        ok_level: Level,
+        err_level: Level,
+        ok_input: impl ErrLog<T>,
+        err_input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_ok(ok_level, ok_input).log(err_level, err_input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_if(Level::Error, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_if(Level::Warn, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_if(Level::Info, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_if(Level::Debug, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_if(self, predicate: impl FnOnce(&E) -> bool, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_if(Level::Trace, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_if(
+        self,
+        level: Level,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+                if level.enabled() && predicate(&err) {
+                    input.log(level, &err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_unless(Level::Error, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_unless(Level::Warn, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_unless(Level::Info, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_unless(Level::Debug, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_unless(
+        self,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_unless(Level::Trace, predicate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_unless(
+        self,
+        level: Level,
+        predicate: impl FnOnce(&E) -> bool,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_if(level, |err| !predicate(err), input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_once(self, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_once(Level::Error, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_once(self, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_once(Level::Warn, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_once(self, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_once(Level::Info, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_once(self, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_once(Level::Debug, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_once(self, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_once(Level::Trace, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_once(self, level: Level, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log(level, Once(input))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_throttled(Level::Error, per, max, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_throttled(Level::Warn, per, max, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_throttled(Level::Info, per, max, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_throttled(Level::Debug, per, max, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_throttled(self, per: Duration, max: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_throttled(Level::Trace, per, max, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_throttled(
+        self,
+        level: Level,
+        per: Duration,
+        max: usize,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log(level, Throttled::new(input, per).max(max))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_every_n(Level::Error, n, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_every_n(Level::Warn, n, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_every_n(Level::Info, n, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_every_n(Level::Debug, n, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_every_n(self, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_every_n(Level::Trace, n, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_every_n(self, level: Level, n: usize, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log(level, EveryN::new(input, n))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_sampled(Level::Error, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_sampled(Level::Warn, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_sampled(Level::Info, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_sampled(Level::Debug, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_sampled(self, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log_sampled(Level::Trace, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_sampled(self, level: Level, rate: f64, input: impl ErrLog<E>) -> Result<T, E> {
+        self.log(level, Sampled::new(input, rate))
+    }
 
-#[macro_export]
-macro_rules! info {
-    ($($arg:tt)*) => {{
-        #[allow(unused_variables)]
-        let args = format_args!($($arg)*);
-        #[cfg(feature = "tracing")]
-        tracing::info!("{}", args);
-        #[cfg(feature = "log")]
-        log::info!("{}", args);
-        #[cfg(feature = "defmt")]
-        defmt::info!("{}", defmt::Display2Format(&args));
-    }};
-}
+    #[inline]
+    #[track_caller]
+    fn warn_escalating(
+        self,
+        threshold: usize,
+        window: Duration,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        self.log_escalating(Level::Warn, threshold, window, input)
+    }
 
-#[macro_export]
-macro_rules! debug {
-    ($($arg:tt)*) => {{
-        #[allow(unused_variables)]
-        let args = format_args!($($arg)*);
-        #[cfg(feature = "tracing")]
-        tracing::debug!("{}", args);
-        #[cfg(feature = "log")]
-        log::debug!("{}", args);
-        #[cfg(feature = "defmt")]
-        defmt::debug!("{}", defmt::Display2Format(&args));
-    }};
+    #[inline]
+    #[track_caller]
+    fn log_escalating(
+        self,
+        level: Level,
+        threshold: usize,
+        window: Duration,
+        input: impl ErrLog<E>,
+    ) -> Result<T, E> {
+        // Without a backend, this compiles away.
+        if cfg!(any(feature = "tracing", feature = "log", feature = "defmt")) {
+            match &self {
+                Ok(_) => call_site::recover(Location::caller()),
+                Err(err) => {
+                    let level = call_site::escalate(Location::caller(), level, threshold, window);
+                    input.log(level, err);
+                }
+            }
+        }
+        self
+    }
 }
 
-#[macro_export]
-macro_rules! trace {
-    ($($arg:tt)*) => {{
-        #[allow(unused_variables)]
-        let args = format_args!($($arg)*);
-        #[cfg(feature = "tracing")]
-        tracing::trace!("{}", args);
-        #[cfg(feature = "log")]
-        log::trace!("{}", args);
-        #[cfg(feature = "defmt")]
-        defmt::trace!("{}", defmt::Display2Format(&args));
-    }};
-}
+impl<T> sealed::Sealed for Option<T> {}
 
-use core::fmt::Display;
+impl<T> NoneContext<T> for Option<T> {
+    #[inline]
+    #[track_caller]
+    fn error(self, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(
+                Level::Error,
+                Some(core::any::type_name::<T>()),
+                __Extra::default(),
+            );
+        }
+        self
+    }
 
-mod sealed {
-    /// A sealed trait to prevent external implementations.
-    pub trait Sealed {}
-}
+    #[inline]
+    #[track_caller]
+    fn warn(self, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(
+                Level::Warn,
+                Some(core::any::type_name::<T>()),
+                __Extra::default(),
+            );
+        }
+        self
+    }
 
+    #[inline]
+    #[track_caller]
+    fn info(self, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(
+                Level::Info,
+                Some(core::any::type_name::<T>()),
+                __Extra::default(),
+            );
+        }
+        self
+    }
 
-pub trait ErrLog<E> {
-    fn error(self, error: &E);
-    fn warn(self, error: &E);
-    fn info(self, error: &E);
-    fn debug(self, error: &E);
-    fn trace(self, error: &E);
-}
+    #[inline]
+    #[track_caller]
+    fn debug(self, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(
+                Level::Debug,
+                Some(core::any::type_name::<T>()),
+                __Extra::default(),
+            );
+        }
+        self
+    }
 
-impl<'a, E> ErrLog<E> for ()
-where
-    E: Display,
-{
     #[inline]
-    fn error(self, error: &E) {
-        error!("{}", error)
+    #[track_caller]
+    fn trace(self, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(
+                Level::Trace,
+                Some(core::any::type_name::<T>()),
+                __Extra::default(),
+            );
+        }
+        self
     }
+
     #[inline]
-    fn warn(self, error: &E) {
-        warn!("{}", error)
+    #[track_caller]
+    fn fatal(self, input: impl NoneLog) -> T {
+        match self {
+            Some(value) => value,
+            None => input.__fatal_none(Some(core::any::type_name::<T>())),
+        }
     }
+
     #[inline]
-    fn info(self, error: &E) {
-        info!("{}", error)
+    #[track_caller]
+    fn log(self, level: Level, input: impl NoneLog) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.__log_none(level, Some(core::any::type_name::<T>()), __Extra::default());
+        }
+        self
     }
+
     #[inline]
-    fn debug(self, error: &E) {
-        debug!("{}", error)
+    #[track_caller]
+    fn log_and_discard(self, level: Level, input: impl NoneLog) {
+        let _ = self.log(level, input);
     }
+
     #[inline]
-    fn trace(self, error: &E) {
-        trace!("{}", error)
+    #[track_caller]
+    fn unwrap_or_log(self, default: T, level: Level, input: impl NoneLog) -> T {
+        self.log(level, input).unwrap_or(default)
     }
-}
 
-impl<E> ErrLog<E> for &str {
     #[inline]
-    fn error(self, error: &E) {
-        error!("{}", self)
+    #[track_caller]
+    fn unwrap_or_else_log(self, f: impl FnOnce() -> T, level: Level, input: impl NoneLog) -> T {
+        self.log(level, input).unwrap_or_else(f)
     }
+
     #[inline]
-    fn warn(self, error: &E) {
-        warn!("{}", self)
+    #[track_caller]
+    fn unwrap_or_default_log(self, level: Level, input: impl NoneLog) -> T
+    where
+        T: Default,
+    {
+        self.log(level, input).unwrap_or_default()
     }
+
     #[inline]
-    fn info(self, error: &E) {
-        info!("{}", self)
+    #[track_caller]
+    fn expect_log(self, level: Level, input: impl NoneLog) -> T {
+        match self {
+            Some(value) => value,
+            None => input.__expect_none(level, Some(core::any::type_name::<T>())),
+        }
     }
+
     #[inline]
-    fn debug(self, error: &E) {
-        debug!("{}", self)
+    #[track_caller]
+    fn warn_some(self, input: impl ErrLog<T>) -> Option<T> {
+        self.log_some(Level::Warn, input)
     }
+
     #[inline]
-    fn trace(self, error: &E) {
-        trace!("{}", self)
+    #[track_caller]
+    fn log_some(self, level: Level, input: impl ErrLog<T>) -> Option<T> {
+        if let Some(value) = &self {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            input.log(level, value);
+        }
+        self
     }
-}
 
-impl<F, E, D> ErrLog<E> for F
-where
-    F: FnOnce(&E) -> D,
-    D: Display,
-{
     #[inline]
-    fn error(self, error: &E) {
-        error!("{}", self(error))
+    #[track_caller]
+    fn error_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_if(Level::Error, predicate, input)
     }
+
     #[inline]
-    fn warn(self, error: &E) {
-        warn!("{}", self(error))
+    #[track_caller]
+    fn warn_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_if(Level::Warn, predicate, input)
     }
+
     #[inline]
-    fn info(self, error: &E) {
-        info!("{}", self(error))
+    #[track_caller]
+    fn info_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_if(Level::Info, predicate, input)
     }
+
     #[inline]
-    fn debug(self, error: &E) {
-        debug!("{}", self(error))
+    #[track_caller]
+    fn debug_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_if(Level::Debug, predicate, input)
     }
+
     #[inline]
-    fn trace(self, error: &E) {
-        trace!("{}", self(error))
+    #[track_caller]
+    fn trace_if(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_if(Level::Trace, predicate, input)
     }
-}
 
-pub trait NoneLog {
-    fn error(self);
-    fn warn(self);
-    fn info(self);
-    fn debug(self);
-    fn trace(self);
-}
+    #[inline]
+    #[track_caller]
+    fn log_if(
+        self,
+        level: Level,
+        predicate: impl FnOnce() -> bool,
+        input: impl NoneLog,
+    ) -> Option<T> {
+        if self.is_none() {
+            #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
+            if level.enabled() && predicate() {
+                input.__log_none(level, Some(core::any::type_name::<T>()), __Extra::default());
+            }
+        }
+        self
+    }
 
-impl NoneLog for &str {
     #[inline]
-    fn error(self) {
-        error!("{}", self)
+    #[track_caller]
+    fn error_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_unless(Level::Error, predicate, input)
     }
+
     #[inline]
-    fn warn(self) {
-        warn!("{}", self)
+    #[track_caller]
+    fn warn_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_unless(Level::Warn, predicate, input)
     }
+
     #[inline]
-    fn info(self) {
-        info!("{}", self)
+    #[track_caller]
+    fn info_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_unless(Level::Info, predicate, input)
     }
+
     #[inline]
-    fn debug(self) {
-        debug!("{}", self)
+    #[track_caller]
+    fn debug_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_unless(Level::Debug, predicate, input)
     }
+
     #[inline]
-    fn trace(self) {
-        trace!("{}", self)
+    #[track_caller]
+    fn trace_unless(self, predicate: impl FnOnce() -> bool, input: impl NoneLog) -> Option<T> {
+        self.log_unless(Level::Trace, predicate, input)
     }
-}
 
-impl<F, D> NoneLog for F
-where
-    F: FnOnce() -> D,
-    D: Display,
-{
     #[inline]
-    fn error(self) {
-        error!("{}", self())
+    #[track_caller]
+    fn log_unless(
+        self,
+        level: Level,
+        predicate: impl FnOnce() -> bool,
+        input: impl NoneLog,
+    ) -> Option<T> {
+        self.log_if(level, || !predicate(), input)
     }
+
     #[inline]
-    fn warn(self) {
-        warn!("{}", self())
+    #[track_caller]
+    fn error_once(self, input: impl NoneLog) -> Option<T> {
+        self.log_once(Level::Error, input)
     }
+
     #[inline]
-    fn info(self) {
-        info!("{}", self())
+    #[track_caller]
+    fn warn_once(self, input: impl NoneLog) -> Option<T> {
+        self.log_once(Level::Warn, input)
     }
+
     #[inline]
-    fn debug(self) {
-        debug!("{}", self())
+    #[track_caller]
+    fn info_once(self, input: impl NoneLog) -> Option<T> {
+        self.log_once(Level::Info, input)
     }
+
     #[inline]
-    fn trace(self) {
-        trace!("{}", self())
+    #[track_caller]
+    fn debug_once(self, input: impl NoneLog) -> Option<T> {
+        self.log_once(Level::Debug, input)
     }
-}
 
-/// For logging a [`Result`] when [`Result::Err`] is encountered.
-pub trait ErrContext<T, E>: sealed::Sealed {
-    /// If [`Result::Err`], logging as "error".
-    fn error(self, input: impl ErrLog<E>) -> Result<T, E>;
-    /// If [`Result::Err`], logging as "warn".
-    fn warn(self, input: impl ErrLog<E>) -> Result<T, E>;
-    /// If [`Result::Err`], logging as "info".
-    fn info(self, input: impl ErrLog<E>) -> Result<T, E>;
-    /// If [`Result::Err`], logging as "debug".
-    fn debug(self, input: impl ErrLog<E>) -> Result<T, E>;
-    /// If [`Result::Err`], logging as "trace".
-    fn trace(self, input: impl ErrLog<E>) -> Result<T, E>;
-}
+    #[inline]
+    #[track_caller]
+    fn trace_once(self, input: impl NoneLog) -> Option<T> {
+        self.log_once(Level::Trace, input)
+    }
 
-/// For logging a [`Option`] when [`Option::None`] is encountered.
-pub trait NoneContext<T>: sealed::Sealed {
-    /// If [`Option::None`], logging as "error".
-    fn error(self, input: impl NoneLog) -> Option<T>;
-    /// If [`Option::None`], logging as "warn".
-    fn warn(self, input: impl NoneLog) -> Option<T>;
-    /// If [`Option::None`], logging as "info".
-    fn info(self, input: impl NoneLog) -> Option<T>;
-    /// If [`Option::None`], logging as "debug".
-    fn debug(self, input: impl NoneLog) -> Option<T>;
-    /// If [`Option::None`], logging as "trace".
-    fn trace(self, input: impl NoneLog) -> Option<T>;
-}
+    #[inline]
+    #[track_caller]
+    fn log_once(self, level: Level, input: impl NoneLog) -> Option<T> {
+        self.log(level, Once(input))
+    }
 
-impl<T, E> sealed::Sealed for Result<T, E> {}
+    #[inline]
+    #[track_caller]
+    fn error_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T> {
+        self.log_throttled(Level::Error, per, max, input)
+    }
 
-impl<T, E> ErrContext<T, E> for Result<T, E> {
     #[inline]
-    fn error(self, input: impl ErrLog<E>) -> Result<T, E> {
-        match self {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.error(&err);
-                Err(err)
-            }
-        }
+    #[track_caller]
+    fn warn_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T> {
+        self.log_throttled(Level::Warn, per, max, input)
     }
 
     #[inline]
-    fn warn(self, input: impl ErrLog<E>) -> Result<T, E> {
-        match self {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.warn(&err);
-                Err(err)
-            }
-        }
+    #[track_caller]
+    fn info_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T> {
+        self.log_throttled(Level::Info, per, max, input)
     }
 
     #[inline]
-    fn info(self, input: impl ErrLog<E>) -> Result<T, E> {
-        match self {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.info(&err);
-                Err(err)
-            }
-        }
+    #[track_caller]
+    fn debug_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T> {
+        self.log_throttled(Level::Debug, per, max, input)
     }
 
     #[inline]
-    fn debug(self, input: impl ErrLog<E>) -> Result<T, E> {
-        match self {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.debug(&err);
-                Err(err)
-            }
-        }
+    #[track_caller]
+    fn trace_throttled(self, per: Duration, max: usize, input: impl NoneLog) -> Option<T> {
+        self.log_throttled(Level::Trace, per, max, input)
     }
 
     #[inline]
-    fn trace(self, input: impl ErrLog<E>) -> Result<T, E> {
-        match self {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.trace(&err);
-                Err(err)
-            }
-        }
+    #[track_caller]
+    fn log_throttled(
+        self,
+        level: Level,
+        per: Duration,
+        max: usize,
+        input: impl NoneLog,
+    ) -> Option<T> {
+        self.log(level, Throttled::new(input, per).max(max))
     }
-}
 
-impl<T> sealed::Sealed for Option<T> {}
+    #[inline]
+    #[track_caller]
+    fn error_every_n(self, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log_every_n(Level::Error, n, input)
+    }
 
-impl<T> NoneContext<T> for Option<T> {
     #[inline]
-    fn error(self, input: impl NoneLog) -> Option<T> {
-        match self {
-            Some(value) => Some(value),
-            None => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.error();
-                None
-            }
-        }
+    #[track_caller]
+    fn warn_every_n(self, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log_every_n(Level::Warn, n, input)
     }
 
     #[inline]
-    fn warn(self, input: impl NoneLog) -> Option<T> {
-        match self {
-            Some(value) => Some(value),
-            None => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.warn();
-                None
-            }
-        }
+    #[track_caller]
+    fn info_every_n(self, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log_every_n(Level::Info, n, input)
     }
 
     #[inline]
-    fn info(self, input: impl NoneLog) -> Option<T> {
-        match self {
-            Some(value) => Some(value),
-            None => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.info();
-                None
-            }
-        }
+    #[track_caller]
+    fn debug_every_n(self, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log_every_n(Level::Debug, n, input)
     }
 
     #[inline]
-    fn debug(self, input: impl NoneLog) -> Option<T> {
-        match self {
-            Some(value) => Some(value),
-            None => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.debug();
-                None
-            }
-        }
+    #[track_caller]
+    fn trace_every_n(self, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log_every_n(Level::Trace, n, input)
     }
 
     #[inline]
-    fn trace(self, input: impl NoneLog) -> Option<T> {
-        match self {
-            Some(value) => Some(value),
-            None => {
-                #[cfg(any(feature = "tracing", feature = "log", feature = "defmt"))]
-                input.trace();
-                None
+    #[track_caller]
+    fn log_every_n(self, level: Level, n: usize, input: impl NoneLog) -> Option<T> {
+        self.log(level, EveryN::new(input, n))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn error_sampled(self, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log_sampled(Level::Error, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_sampled(self, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log_sampled(Level::Warn, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn info_sampled(self, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log_sampled(Level::Info, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn debug_sampled(self, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log_sampled(Level::Debug, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn trace_sampled(self, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log_sampled(Level::Trace, rate, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_sampled(self, level: Level, rate: f64, input: impl NoneLog) -> Option<T> {
+        self.log(level, Sampled::new(input, rate))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn warn_escalating(self, threshold: usize, window: Duration, input: impl NoneLog) -> Option<T> {
+        self.log_escalating(Level::Warn, threshold, window, input)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn log_escalating(
+        self,
+        level: Level,
+        threshold: usize,
+        window: Duration,
+        input: impl NoneLog,
+    ) -> Option<T> {
+        // Without a backend, this compiles away.
+        if cfg!(any(feature = "tracing", feature = "log", feature = "defmt")) {
+            match &self {
+                Some(_) => call_site::recover(Location::caller()),
+                None => {
+                    let level = call_site::escalate(Location::caller(), level, threshold, window);
+                    let type_name = Some(core::any::type_name::<T>());
+                    input.__log_none(level, type_name, __Extra::default());
+                }
             }
         }
+        self
     }
 }
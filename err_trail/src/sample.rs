@@ -0,0 +1,40 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// The state of the generator deciding which events [`Sampled`](crate::Sampled) and the `_sampled`
+/// macros log. 32 bits wide, since 64 bit atomics are missing on many embedded targets.
+static STATE: AtomicU32 = AtomicU32::new(0x2545_f491);
+
+/// Seeds the generator deciding which events [`Sampled`](crate::Sampled) and the `_sampled`
+/// macros log. It starts from the same seed in every process, so seed it with e.g. the time for
+/// different events to be sampled on each run, or with a constant in tests.
+///
+/// ```rust
+/// err_trail::seed_sampling(42);
+/// ```
+pub fn seed_sampling(seed: u32) {
+    STATE.store(seed, Ordering::Relaxed);
+}
+
+/// The next number of a SplitMix32 sequence, with the finalizer of MurmurHash3.
+fn next() -> u32 {
+    const GAMMA: u32 = 0x9e37_79b9;
+    let mut z = STATE
+        .fetch_add(GAMMA, Ordering::Relaxed)
+        .wrapping_add(GAMMA);
+    z = (z ^ (z >> 16)).wrapping_mul(0x85eb_ca6b);
+    z = (z ^ (z >> 13)).wrapping_mul(0xc2b2_ae35);
+    z ^ (z >> 16)
+}
+
+/// Whether to log an event sampled with a chance of `rate`. Rates of 1.0 or more always log, 0.0 or
+/// less never do.
+#[doc(hidden)]
+pub fn sample(rate: f64) -> bool {
+    if rate >= 1.0 {
+        true
+    } else if rate > 0.0 {
+        (next() as f64) < rate * 4_294_967_296.0
+    } else {
+        false
+    }
+}
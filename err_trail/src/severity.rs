@@ -0,0 +1,35 @@
+use crate::Level;
+
+#[cfg(doc)]
+use crate::ErrContext;
+
+/// An error that chooses the level it is logged at, for [`ErrContext::log_auto`]. This keeps the
+/// logging policy with the error definition instead of at each call site.
+///
+/// With the `derive` feature, this can be derived with `#[severity(..)]` attributes on the type
+/// and on enum variants. Variants without one use the type's level, or "error".
+///
+/// ```rust
+/// # #[cfg(feature = "derive")] {
+/// use err_trail::{Level, Severity};
+///
+/// #[derive(Severity)]
+/// enum FetchError {
+///     #[severity(warn)]
+///     TimedOut,
+///     Corrupt { reason: String },
+/// }
+///
+/// assert_eq!(FetchError::TimedOut.level(), Level::Warn);
+/// # }
+/// ```
+pub trait Severity {
+    /// The level this error is logged at.
+    fn level(&self) -> Level;
+}
+
+impl<T: Severity + ?Sized> Severity for &T {
+    fn level(&self) -> Level {
+        (**self).level()
+    }
+}
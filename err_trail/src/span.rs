@@ -0,0 +1,259 @@
+#[cfg(any(feature = "log", feature = "defmt"))]
+use core::fmt::Write;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+
+/// A period of time in which events happen, with a name and fields, created by
+/// [`span!`](crate::span). With the `tracing` backend it is a [`tracing::Span`]. With `log` and
+/// `defmt`, which have no spans, the name and fields of the entered spans are prefixed onto the
+/// events inside them instead, e.g. "request{id=7}:db: query failed". This needs the `std` feature,
+/// or `single-threaded` for targets with one thread, otherwise nothing is prefixed. Without a
+/// backend it does nothing.
+///
+/// Like a [`tracing::Span`], it should not stay entered across an `.await`.
+#[derive(Debug, Clone)]
+pub struct Span {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(any(feature = "log", feature = "defmt"))]
+    prefix: Option<Buf<PREFIX_CAPACITY>>,
+}
+
+impl Span {
+    /// A span that is disabled, so entering it does nothing.
+    pub fn none() -> Self {
+        Span {
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            prefix: None,
+        }
+    }
+
+    /// Renders the prefix for `log` and `defmt`, e.g. "request{id=7}". `fields` is empty or starts
+    /// with a space, e.g. " id=7".
+    #[doc(hidden)]
+    pub fn __new(name: &'static str, fields: fmt::Arguments<'_>) -> Self {
+        #[allow(unused_mut)]
+        let mut span = Span::none();
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        {
+            let mut prefix = Buf::new();
+            let _ = prefix.write_str(name);
+            let start = prefix.len;
+            let _ = prefix.write_fmt(fields);
+            if prefix.len > start {
+                prefix.bytes[start] = b'{';
+                let _ = prefix.write_str("}");
+            }
+            span.prefix = Some(prefix);
+        }
+        span
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "tracing")]
+    pub fn __tracing(mut self, span: tracing::Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Enters the span until the returned guard is dropped.
+    pub fn enter(&self) -> Entered<'_> {
+        Entered {
+            #[cfg(feature = "tracing")]
+            _entered: self.span.enter(),
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            scope: self
+                .prefix
+                .as_ref()
+                .and_then(|prefix| scope::push(prefix.as_str())),
+            span: PhantomData,
+        }
+    }
+
+    /// Calls `f` with the span entered.
+    pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _entered = self.enter();
+        f()
+    }
+}
+
+/// Keeps a [`Span`] entered until dropped. Created by [`Span::enter`].
+#[must_use = "the span is exited as soon as the guard is dropped"]
+pub struct Entered<'a> {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::Entered<'a>,
+    /// The length of the scope before the span was entered, if it was pushed.
+    #[cfg(any(feature = "log", feature = "defmt"))]
+    scope: Option<usize>,
+    span: PhantomData<&'a Span>,
+}
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        if let Some(len) = self.scope {
+            scope::pop(len);
+        }
+    }
+}
+
+/// How many bytes of the name and fields of a span are kept. Longer ones are truncated.
+#[cfg(any(feature = "log", feature = "defmt"))]
+const PREFIX_CAPACITY: usize = 64;
+
+/// How many bytes of the entered spans are prefixed onto events. Deeper ones are truncated.
+#[cfg(any(feature = "log", feature = "defmt"))]
+const SCOPE_CAPACITY: usize = 256;
+
+/// A string of at most `N` bytes, truncating what does not fit at a character boundary.
+#[cfg(any(feature = "log", feature = "defmt"))]
+#[derive(Clone)]
+struct Buf<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+#[cfg(any(feature = "log", feature = "defmt"))]
+impl<const N: usize> Buf<N> {
+    const fn new() -> Self {
+        Buf {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole characters are written.
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+#[cfg(any(feature = "log", feature = "defmt"))]
+impl<const N: usize> Write for Buf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(N - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.bytes[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        if end == s.len() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+#[cfg(any(feature = "log", feature = "defmt"))]
+impl<const N: usize> fmt::Debug for Buf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// The entered spans, e.g. "request{id=7}:db", for `log` and `defmt`. Per thread with the `std`
+/// feature, otherwise for the whole program with the `single-threaded` feature, and not kept at
+/// all without either, since the spans of one thread would prefix the events of others. Access is
+/// never waited for, so events logged while it is in use, e.g. from an interrupt, have no prefix.
+#[cfg(any(feature = "log", feature = "defmt"))]
+mod scope {
+    use core::fmt::Write;
+
+    use super::{Buf, SCOPE_CAPACITY};
+
+    pub(super) type Scope = Buf<SCOPE_CAPACITY>;
+
+    #[cfg(feature = "std")]
+    std::thread_local! {
+        static SCOPE: core::cell::RefCell<Scope> = const { core::cell::RefCell::new(Buf::new()) };
+    }
+
+    #[cfg(feature = "std")]
+    pub(super) fn with<R>(f: impl FnOnce(&mut Scope) -> R) -> Option<R> {
+        SCOPE
+            .try_with(|scope| scope.try_borrow_mut().ok().map(|mut scope| f(&mut scope)))
+            .ok()
+            .flatten()
+    }
+
+    #[cfg(all(not(feature = "std"), feature = "single-threaded"))]
+    struct Global {
+        locked: core::sync::atomic::AtomicBool,
+        scope: core::cell::UnsafeCell<Scope>,
+    }
+
+    // SAFETY: `scope` is only accessed by whoever set `locked`.
+    #[cfg(all(not(feature = "std"), feature = "single-threaded"))]
+    unsafe impl Sync for Global {}
+
+    #[cfg(all(not(feature = "std"), feature = "single-threaded"))]
+    static SCOPE: Global = Global {
+        locked: core::sync::atomic::AtomicBool::new(false),
+        scope: core::cell::UnsafeCell::new(Buf::new()),
+    };
+
+    #[cfg(all(not(feature = "std"), feature = "single-threaded"))]
+    pub(super) fn with<R>(f: impl FnOnce(&mut Scope) -> R) -> Option<R> {
+        use core::sync::atomic::Ordering;
+
+        SCOPE
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // SAFETY: `locked` was set above, and `f` cannot reach `with` again.
+        let result = f(unsafe { &mut *SCOPE.scope.get() });
+        SCOPE.locked.store(false, Ordering::Release);
+        Some(result)
+    }
+
+    #[cfg(not(any(feature = "std", feature = "single-threaded")))]
+    pub(super) fn with<R>(_: impl FnOnce(&mut Scope) -> R) -> Option<R> {
+        None
+    }
+
+    /// Appends a span to the scope, returning the previous length to [`pop`] back to.
+    pub(super) fn push(prefix: &str) -> Option<usize> {
+        with(|scope| {
+            let len = scope.len;
+            if len > 0 {
+                let _ = scope.write_str(":");
+            }
+            let _ = scope.write_str(prefix);
+            len
+        })
+    }
+
+    pub(super) fn pop(len: usize) {
+        with(|scope| scope.len = len);
+    }
+}
+
+/// The entered spans when an event is logged, displayed before its message for `log` and `defmt`,
+/// e.g. "request{id=7}:db: ".
+#[doc(hidden)]
+pub struct __Scope {
+    #[cfg(any(feature = "log", feature = "defmt"))]
+    scope: Option<Buf<SCOPE_CAPACITY>>,
+}
+
+/// The entered spans, to prefix onto an event.
+#[doc(hidden)]
+pub fn __scope() -> __Scope {
+    __Scope {
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        scope: scope::with(|scope| scope.clone()).filter(|scope| scope.len > 0),
+    }
+}
+
+impl Display for __Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        if let Some(scope) = &self.scope {
+            write!(f, "{}: ", scope.as_str())?;
+        }
+        Ok(())
+    }
+}
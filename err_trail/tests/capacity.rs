@@ -0,0 +1,64 @@
+//! Fills the table of `Once` call sites, which is process-wide, in its own binary.
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use err_trail::{ErrContext, Once};
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOGS.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[track_caller]
+fn o() {
+    let _ = Err::<(), _>("full").warn(Once(()));
+}
+
+/// 130 call sites of `Once`, each on its own column.
+#[rustfmt::skip]
+fn call_sites() {
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+    o(); o(); o(); o(); o(); o(); o(); o(); o(); o();
+}
+
+#[test]
+fn test_overflow_warns_once() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    call_sites();
+    call_sites();
+
+    let logs = LOGS.lock().unwrap();
+    let warnings: Vec<&String> = logs
+        .iter()
+        .filter(|log| log.starts_with("more than"))
+        .collect();
+    assert_eq!(
+        warnings,
+        ["more than 128 call sites are tracked for once, further call sites are not tracked"]
+    );
+    // The first 128 call sites log once, the other 2 every time.
+    assert_eq!(
+        logs.iter().filter(|log| *log == "full").count(),
+        128 + 2 * 2
+    );
+}
@@ -0,0 +1,122 @@
+//! Deduplication is a process-wide mode, so it is tested in its own binary, in a single test, to
+//! not suppress the repeated messages of other tests.
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use err_trail::{ErrContext, NoneContext, flush_dedup, set_dedup, warn};
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let log = format!(
+            "[{} {}] {}:{} {}",
+            record.level(),
+            record.target(),
+            record.file().unwrap_or_default(),
+            record.line().unwrap_or_default(),
+            record.args()
+        );
+        LOGS.lock().unwrap().push(log);
+    }
+
+    fn flush(&self) {}
+}
+
+fn take_logs() -> Vec<String> {
+    std::mem::take(&mut *LOGS.lock().unwrap())
+}
+
+#[test]
+fn test_dedup() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+    set_dedup(true);
+
+    let line = line!() + 2;
+    for attempt in [1, 1, 1, 2] {
+        warn!("retry {}", attempt);
+    }
+    let at = |level: &str, message: &str| {
+        format!("[{level} dedup] err_trail/tests/dedup.rs:{line} {message}")
+    };
+    assert_eq!(
+        take_logs(),
+        [
+            at("WARN", "retry 1"),
+            at("WARN", "last message repeated 2 times"),
+            at("WARN", "retry 2"),
+        ]
+    );
+
+    // The repeats are reported with the target of the repeated message.
+    let line = line!() + 2;
+    for _ in 0..3 {
+        warn!(target: "db::pool", "pool exhausted");
+    }
+    flush_dedup();
+    let at = |message: &str| format!("[WARN db::pool] err_trail/tests/dedup.rs:{line} {message}");
+    assert_eq!(
+        take_logs(),
+        [at("pool exhausted"), at("last message repeated 2 times")]
+    );
+
+    // Targets only known at runtime work too, though not with `tracing`, which needs constants.
+    #[cfg(not(feature = "tracing"))]
+    {
+        let line = line!() + 1;
+        let exhausted = |target: &'static str| warn!(target: target, "pool exhausted");
+        for target in ["db::replica"; 3] {
+            exhausted(target);
+        }
+        flush_dedup();
+        let at =
+            |message: &str| format!("[WARN db::replica] err_trail/tests/dedup.rs:{line} {message}");
+        assert_eq!(
+            take_logs(),
+            [at("pool exhausted"), at("last message repeated 2 times")]
+        );
+    }
+
+    let line = line!() + 2;
+    let refused = || {
+        let _ = Err::<(), _>("connection refused").warn(());
+        let _ = None::<u8>.error("no reply");
+    };
+    for _ in 0..3 {
+        refused();
+    }
+    flush_dedup();
+    let logs = take_logs();
+    let at = |line: u32, level: &str, message: &str| {
+        format!("[{level} err_trail] err_trail/tests/dedup.rs:{line} {message}")
+    };
+    assert_eq!(logs.len(), 4);
+    assert_eq!(logs[0], at(line, "WARN", "connection refused"));
+    assert_eq!(logs[1], at(line + 1, "ERROR", "no reply"));
+    assert!(logs.contains(&at(line, "WARN", "last message repeated 2 times")));
+    assert!(logs.contains(&at(line + 1, "ERROR", "last message repeated 2 times")));
+
+    // Flushing keeps the last messages, so their repeats are still suppressed.
+    refused();
+    set_dedup(false);
+    let logs = take_logs();
+    assert_eq!(logs.len(), 2);
+    assert!(
+        logs.iter()
+            .all(|log| log.ends_with(" last message repeated 1 times"))
+    );
+
+    for _ in 0..2 {
+        warn!("not deduplicated");
+    }
+    assert_eq!(take_logs().len(), 2);
+}
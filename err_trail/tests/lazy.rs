@@ -0,0 +1,81 @@
+//! The `log` max level is process-wide, so laziness through `log::log_enabled!` is tested in its
+//! own binary, to not filter the events of other tests.
+#![cfg(feature = "log")]
+
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use err_trail::{ErrContext, NoneContext, debug, trace};
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOGS.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn test_disabled_level_is_not_evaluated() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    let evaluated = Cell::new(0);
+    let count = || evaluated.set(evaluated.get() + 1);
+
+    let _ = Err::<(), _>("trace").trace(|e: &&str| {
+        count();
+        format!("A trace occurred: `{}`", e)
+    });
+    let _ = None::<()>.debug(|| {
+        count();
+        "Lazy debug context"
+    });
+    let _ = Err::<(), _>("debug").debug_if(
+        |_| {
+            count();
+            true
+        },
+        (),
+    );
+    let _ = None::<()>.trace_unless(
+        || {
+            count();
+            false
+        },
+        (),
+    );
+    trace!("{}", {
+        count();
+        "direct trace"
+    });
+    debug!("{}", {
+        count();
+        "direct debug"
+    });
+    assert_eq!(evaluated.get(), 0);
+    assert!(LOGS.lock().unwrap().is_empty());
+
+    let _ = Err::<(), _>("info").info(|e: &&str| {
+        count();
+        format!("An info occurred: `{}`", e)
+    });
+    let _ = Err::<(), _>("warn").warn_if(
+        |_| {
+            count();
+            true
+        },
+        (),
+    );
+    assert_eq!(evaluated.get(), 2);
+    assert_eq!(*LOGS.lock().unwrap(), ["An info occurred: `info`", "warn"]);
+}
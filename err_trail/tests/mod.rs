@@ -1,7 +1,119 @@
+#[cfg(test)]
+mod chain_error {
+    use std::fmt;
+
+    #[derive(Debug)]
+    pub struct Layered {
+        message: &'static str,
+        source: Option<Box<Layered>>,
+    }
+
+    impl fmt::Display for Layered {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl std::error::Error for Layered {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.source.as_deref().map(|source| source as _)
+        }
+    }
+
+    pub fn layered_from(messages: &[&'static str]) -> Layered {
+        Layered {
+            message: messages[0],
+            source: (messages.len() > 1).then(|| Box::new(layered_from(&messages[1..]))),
+        }
+    }
+
+    /// "outer" caused by "middle" caused by "root".
+    pub fn layered() -> Layered {
+        layered_from(&["outer", "middle", "root"])
+    }
+
+    #[test]
+    fn test_chain_format() {
+        use err_trail::{Chain, chain};
+
+        let error = layered();
+        assert_eq!(chain(&error).to_string(), "outer: middle: root");
+        assert_eq!(
+            Chain::separator(" <- ").display(&error).to_string(),
+            "outer <- middle <- root"
+        );
+        assert_eq!(
+            Chain::max_depth(1).display(&error).to_string(),
+            "outer: middle: ..."
+        );
+        assert_eq!(
+            Chain::caused_by().display(&error).to_string(),
+            "outer\n\nCaused by:\n    0: middle\n    1: root"
+        );
+        assert_eq!(chain(&layered_from(&["alone"])).to_string(), "alone");
+    }
+}
+
+#[cfg(test)]
+mod level {
+    use err_trail::Level;
+
+    #[test]
+    fn test_level_from_str() {
+        assert_eq!("error".parse(), Ok(Level::Error));
+        assert_eq!("WARN".parse(), Ok(Level::Warn));
+        assert_eq!("Warning".parse(), Ok(Level::Warn));
+        assert_eq!(" info ".parse(), Ok(Level::Info));
+        assert_eq!("debug".parse(), Ok(Level::Debug));
+        assert_eq!("trace".parse(), Ok(Level::Trace));
+        assert!("verbose".parse::<Level>().is_err());
+    }
+
+    #[test]
+    fn test_level_display_round_trip() {
+        for level in Level::ALL {
+            assert_eq!(level.to_string().parse(), Ok(level));
+        }
+        assert_eq!(Level::Error.to_string(), "ERROR");
+        assert!(Level::Error < Level::Trace);
+    }
+}
+
+/// A clock the tests advance by hand. Tests that use it or reset call sites hold [`lock`], so they
+/// do not interfere with each other.
+#[cfg(any(feature = "tracing", feature = "log"))]
+#[cfg(test)]
+mod test_clock {
+    use err_trail::Clock;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Duration;
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    struct TestClock;
+
+    impl Clock for TestClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(NOW.load(Ordering::SeqCst))
+        }
+    }
+
+    pub fn lock() -> MutexGuard<'static, ()> {
+        let _ = err_trail::set_clock(&TestClock);
+        LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn advance(duration: Duration) {
+        NOW.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
 #[cfg(feature = "tracing")]
 #[cfg(test)]
 mod tracing {
-    use err_trail::{ErrContext, NoneContext, debug, error, info, trace, warn};
+    use err_trail::{ErrContext, Level, NoneContext, debug, error, fatal, info, log, trace, warn};
     use flaky_test::flaky_test;
     use tracing_test::traced_test;
 
@@ -203,6 +315,103 @@ mod tracing {
         assert!(logs_contain("Lazy trace context"));
     }
 
+    #[traced_test]
+    #[flaky_test]
+    fn test_caller_location() {
+        let result: Result<(), &str> = Err("error");
+        let _ = result.error("Located error");
+        let line = line!() - 1;
+
+        assert!(logs_contain("Located error"));
+        assert!(logs_contain("tests/mod.rs"));
+        assert!(logs_contain(&format!("code.lineno={}", line)));
+    }
+
+    #[traced_test]
+    #[flaky_test]
+    fn test_option_caller_location() {
+        let option: Option<()> = None;
+        let _ = option.warn(|| "Located none");
+        let line = line!() - 1;
+
+        assert!(logs_contain("Located none"));
+        assert!(logs_contain(&format!("code.lineno={}", line)));
+    }
+
+    #[traced_test]
+    #[flaky_test]
+    fn test_trail() {
+        let result: Result<u8, &str> = Err("boom");
+        assert_eq!(
+            err_trail::trail!(warn, result, "failed to load {}", "a"),
+            Err("boom")
+        );
+        let option: Option<u8> = None;
+        let _ = err_trail::trail!(error, option);
+        let _ = err_trail::trail!(error, Some(1), "not logged");
+
+        assert!(logs_contain(
+            "WARN test_trail: r#mod::tracing: failed to load a: boom"
+        ));
+        assert!(logs_contain(
+            "ERROR test_trail: r#mod::tracing: expected Some("
+        ));
+        assert!(!logs_contain("not logged"));
+    }
+
+    // The `log` test logger accepts every level, so it would evaluate the closures itself.
+    #[cfg(not(feature = "log"))]
+    #[test]
+    fn test_disabled_level_is_not_evaluated() {
+        use std::cell::Cell;
+
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let evaluated = Cell::new(0);
+
+            let result: Result<(), &str> = Err("trace");
+            let _ = result.trace(|e: &&str| {
+                evaluated.set(evaluated.get() + 1);
+                format!("A trace occurred: `{}`", e)
+            });
+            let option: Option<()> = None;
+            let _ = option.debug(|| {
+                evaluated.set(evaluated.get() + 1);
+                "Lazy debug context"
+            });
+            trace!("{}", {
+                evaluated.set(evaluated.get() + 1);
+                "direct trace"
+            });
+            assert_eq!(evaluated.get(), 0);
+
+            let result: Result<(), &str> = Err("info");
+            let _ = result.info(|e: &&str| {
+                evaluated.set(evaluated.get() + 1);
+                format!("An info occurred: `{}`", e)
+            });
+            assert_eq!(evaluated.get(), 1);
+
+            let _ = Err::<(), _>("debug").debug_if(
+                |_| {
+                    evaluated.set(evaluated.get() + 1);
+                    true
+                },
+                (),
+            );
+            let _ = None::<()>.trace_unless(
+                || {
+                    evaluated.set(evaluated.get() + 1);
+                    false
+                },
+                "none",
+            );
+            assert_eq!(evaluated.get(), 1);
+        });
+    }
+
     #[traced_test]
     #[test]
     fn test_macros_direct_call() {
@@ -219,6 +428,718 @@ mod tracing {
         assert!(logs_contain("direct trace"));
     }
 
+    #[traced_test]
+    #[test]
+    fn test_macro_fields() {
+        let user_id = 7;
+        let error = "refused";
+        error!(user_id = user_id, retries = 3, cause = %error, path = ?"/tmp"; "connect failed: {}", error);
+
+        assert!(logs_contain("connect failed: refused"));
+        assert!(logs_contain("user_id=7"));
+        assert!(logs_contain("retries=3"));
+        assert!(logs_contain("cause=refused"));
+        assert!(logs_contain("path=\"/tmp\""));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_macro_metadata() {
+        let span = tracing::info_span!("pool_span");
+        error!(target: "db::pool", "pool exhausted");
+        warn!(name: "retry_event", target: "db::pool", attempt = 2; "retrying");
+        info!(parent: &span, "checked out");
+
+        assert!(logs_contain("db::pool: pool exhausted"));
+        assert!(logs_contain("db::pool: retrying attempt=2"));
+        assert!(logs_contain("pool_span: r#mod::tracing: checked out"));
+    }
+
+    #[traced_test]
+    #[flaky_test]
+    fn test_log_level() {
+        let result: Result<(), &str> = Err("error");
+        let _ = result.log(Level::Warn, |e: &&str| format!("Runtime level: `{}`", e));
+        let option: Option<()> = None;
+        let _ = option.log(Level::Debug, "Runtime level none");
+
+        assert!(logs_contain("WARN"));
+        assert!(logs_contain("Runtime level: `error`"));
+        assert!(logs_contain("DEBUG"));
+        assert!(logs_contain("Runtime level none"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_fatal() {
+        let result: Result<u32, &str> = Ok(1);
+        assert_eq!(result.fatal("Not logged"), 1);
+
+        let result: Result<u32, &str> = Err("corrupt");
+        let panic = std::panic::catch_unwind(|| result.fatal(|e: &&str| format!("Fatal: `{}`", e)))
+            .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Fatal: `corrupt`");
+        assert!(logs_contain("ERROR"));
+        assert!(logs_contain("Fatal: `corrupt`"));
+        assert!(!logs_contain("Not logged"));
+
+        let option: Option<u32> = None;
+        let panic = std::panic::catch_unwind(|| option.fatal("Fatal none")).unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Fatal none");
+        assert!(logs_contain("Fatal none"));
+
+        let panic =
+            std::panic::catch_unwind(|| fatal!(code = 3; "Fatal macro: {}", 1)).unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Fatal macro: 1");
+        assert!(logs_contain("Fatal macro: 1 code=3"));
+    }
+
+    #[traced_test]
+    #[flaky_test]
+    fn test_chain() {
+        use crate::chain_error::layered;
+        use err_trail::Chain;
+
+        let _ = Err::<(), _>(layered()).error(Chain);
+        let _ = Err::<(), _>(layered()).warn(Chain::separator(" <- ").max_depth(1));
+
+        assert!(logs_contain("outer: middle: root"));
+        assert!(logs_contain("outer <- middle <- ..."));
+    }
+
+    #[traced_test]
+    #[flaky_test]
+    fn test_debug_inputs() {
+        use err_trail::{Alt, Dbg, PrettyDbg};
+
+        let _ = Err::<(), _>(vec![7]).error(Dbg);
+        let _ = Err::<(), _>(vec![8]).warn(PrettyDbg);
+        let _ = Err::<(), _>(std::fmt::Error).info(Alt);
+
+        assert!(logs_contain("err_trail: [7]"));
+        assert!(logs_contain("WARN test_debug_inputs: err_trail: ["));
+        assert!(logs_contain(
+            "err_trail: an error occurred when formatting an argument"
+        ));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_context() {
+        use err_trail::context;
+
+        let _ = Err::<(), _>("file not found").error(context("failed to load config"));
+        let _ = Err::<(), _>("timeout")
+            .warn(context(String::from("retrying")).template("{error} ({context})"));
+        let _ = Err::<(), _>(3)
+            .info(context(format_args!("step {}", 2)).template("{context} - {x} {error}"));
+
+        assert!(logs_contain(
+            "err_trail: failed to load config: file not found"
+        ));
+        assert!(logs_contain("err_trail: timeout (retrying)"));
+        assert!(logs_contain("err_trail: step 2 - {x} 3"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_consuming() {
+        Err::<(), _>("discarded").log_and_discard(Level::Warn, ());
+        assert_eq!(Err::<u8, _>("ok_or").ok_or_log(Level::Info, ()), None);
+        assert_eq!(Err::<u8, _>("or").unwrap_or_log(1, Level::Error, ()), 1);
+        assert_eq!(
+            Err::<usize, _>("or_else").unwrap_or_else_log(|e| e.len(), Level::Error, ()),
+            7
+        );
+        assert_eq!(Ok::<u8, &str>(5).unwrap_or_default_log(Level::Error, ()), 5);
+        assert_eq!(
+            None::<u8>.unwrap_or_default_log(Level::Debug, "defaulted"),
+            0
+        );
+        assert_eq!(Some(3).expect_log(Level::Error, "unreachable"), 3);
+        let panic = std::panic::catch_unwind(|| None::<u8>.expect_log(Level::Error, "expected"))
+            .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "expected");
+        let panic = std::panic::catch_unwind(|| {
+            Err::<u8, _>("corrupt").expect_log(Level::Warn, |e: &&str| format!("Expect: `{}`", e))
+        })
+        .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Expect: `corrupt`");
+        let panic = std::panic::catch_unwind(|| {
+            Err::<u8, _>("once").expect_log(Level::Info, err_trail::Once(()))
+        })
+        .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "once");
+
+        assert!(logs_contain("WARN test_consuming: err_trail: discarded"));
+        assert!(logs_contain("INFO test_consuming: err_trail: ok_or"));
+        assert!(logs_contain("err_trail: or_else"));
+        assert!(logs_contain("DEBUG test_consuming: err_trail: defaulted"));
+        assert!(!logs_contain("unreachable"));
+        assert!(logs_contain("ERROR test_consuming: err_trail: expected"));
+        assert!(logs_contain(
+            "WARN test_consuming: err_trail: Expect: `corrupt`"
+        ));
+        assert!(logs_contain("INFO test_consuming: err_trail: once"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_success_side() {
+        let _ = Ok::<_, ()>("user 1").info_ok(|user: &&str| format!("{} deleted file 2", user));
+        let _ = Err::<u8, _>("denied").log_result(Level::Info, Level::Warn, "not logged", ());
+        let _ = Ok::<u8, &str>(9).log_result(Level::Debug, Level::Warn, (), ());
+        let _ = Some("stale").warn_some(());
+
+        assert!(logs_contain(
+            "INFO test_success_side: err_trail: user 1 deleted file 2"
+        ));
+        assert!(logs_contain("WARN test_success_side: err_trail: denied"));
+        assert!(!logs_contain("not logged"));
+        assert!(logs_contain("DEBUG test_success_side: err_trail: 9"));
+        assert!(logs_contain("WARN test_success_side: err_trail: stale"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_predicates() {
+        use std::io::{Error, ErrorKind};
+
+        let timed_out = || Err::<(), _>(Error::from(ErrorKind::TimedOut));
+        let not_found = || Err::<(), _>(Error::from(ErrorKind::NotFound));
+        let _ = timed_out().warn_if(|e| e.kind() == ErrorKind::TimedOut, "timed out");
+        let _ = not_found().warn_if(|e| e.kind() == ErrorKind::TimedOut, "not found");
+        let _ = not_found().error_unless(|e| e.kind() == ErrorKind::TimedOut, "unless");
+        let retrying = true;
+        let _ = None::<u8>.log_if(Level::Info, || retrying, "missing while retrying");
+        let _ = None::<u8>.info_unless(|| retrying, "missing without retry");
+
+        assert!(logs_contain("WARN test_predicates: err_trail: timed out"));
+        assert!(!logs_contain("not found"));
+        assert!(logs_contain("ERROR test_predicates: err_trail: unless"));
+        assert!(logs_contain(
+            "INFO test_predicates: err_trail: missing while retrying"
+        ));
+        assert!(!logs_contain("missing without retry"));
+    }
+
+    #[cfg(feature = "derive")]
+    #[traced_test]
+    #[test]
+    fn test_severity() {
+        use err_trail::Severity;
+
+        #[derive(Severity)]
+        #[severity(info)]
+        enum FetchError {
+            #[severity(warn)]
+            TimedOut,
+            #[severity(debug)]
+            Retry(u8),
+            Cached {
+                key: &'static str,
+            },
+        }
+
+        impl std::fmt::Display for FetchError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    FetchError::TimedOut => write!(f, "timed out"),
+                    FetchError::Retry(attempt) => write!(f, "retry {}", attempt),
+                    FetchError::Cached { key } => write!(f, "cached {}", key),
+                }
+            }
+        }
+
+        #[derive(Severity)]
+        struct Fatal;
+
+        #[derive(Severity)]
+        enum Never {}
+
+        let _ = Err::<(), _>(FetchError::TimedOut).log_auto(());
+        let _ = Err::<(), _>(FetchError::Retry(2)).log_auto(());
+        let _ = Err::<(), _>(FetchError::Cached { key: "a" }).log_auto(());
+
+        assert!(logs_contain("WARN test_severity: err_trail: timed out"));
+        assert!(logs_contain("DEBUG test_severity: err_trail: retry 2"));
+        assert!(logs_contain("INFO test_severity: err_trail: cached a"));
+        assert_eq!(Fatal.level(), Level::Error);
+        assert_eq!(None::<Never>.as_ref().map(Severity::level), None);
+    }
+
+    #[cfg(feature = "derive")]
+    #[traced_test]
+    #[test]
+    fn test_error_code() {
+        use err_trail::{Catalog, Code, ErrorCode};
+
+        /// Fetching failed.
+        #[derive(ErrorCode)]
+        enum FetchError {
+            /// The upstream did not answer
+            /// in time.
+            #[code("E1042")]
+            TimedOut,
+            #[code("E1043")]
+            Corrupt(&'static str),
+        }
+
+        impl std::fmt::Display for FetchError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    FetchError::TimedOut => write!(f, "timed out"),
+                    FetchError::Corrupt(reason) => write!(f, "corrupt: {}", reason),
+                }
+            }
+        }
+
+        /// A "quoted" | piped description.
+        #[derive(ErrorCode)]
+        #[code("E2001")]
+        struct PoolExhausted;
+
+        #[derive(ErrorCode)]
+        enum Never {}
+
+        let _ = Err::<(), _>(FetchError::Corrupt("bad checksum")).error(Code);
+
+        assert!(logs_contain("err_trail: [E1043] corrupt: bad checksum"));
+        assert_eq!(FetchError::TimedOut.code(), "E1042");
+        assert_eq!(PoolExhausted.code(), "E2001");
+        assert_eq!(None::<Never>.as_ref().map(ErrorCode::code), None);
+        assert!(Never::CODES.is_empty());
+
+        let catalog = Catalog::new(&[FetchError::CODES, PoolExhausted::CODES]);
+        assert_eq!(
+            catalog.json().to_string(),
+            concat!(
+                r#"[{"code":"E1042","name":"FetchError::TimedOut","description":"The upstream did not answer in time."},"#,
+                r#"{"code":"E1043","name":"FetchError::Corrupt","description":""},"#,
+                r#"{"code":"E2001","name":"PoolExhausted","description":"A \"quoted\" | piped description."}]"#,
+            )
+        );
+        assert_eq!(
+            catalog.markdown().to_string(),
+            "| Code | Name | Description |\n\
+             | --- | --- | --- |\n\
+             | E1042 | FetchError::TimedOut | The upstream did not answer in time. |\n\
+             | E1043 | FetchError::Corrupt |  |\n\
+             | E2001 | PoolExhausted | A \"quoted\" \\| piped description. |\n"
+        );
+    }
+
+    #[cfg(feature = "catalog")]
+    #[test]
+    fn test_catalog_all() {
+        use err_trail::{Catalog, ErrorCode};
+
+        #[derive(ErrorCode)]
+        enum StoreError {
+            #[code("E3002")]
+            Full,
+            /// The key is not in the store.
+            #[code("E3001")]
+            Missing,
+        }
+
+        // Generic types are not registered.
+        #[derive(ErrorCode)]
+        #[code("E3003")]
+        struct Wrapped<T>(T);
+
+        let markdown = Catalog::all().markdown().to_string();
+        let rows: Vec<&str> = markdown.lines().skip(2).collect();
+        let mut sorted = rows.clone();
+        sorted.sort();
+        assert_eq!(rows, sorted);
+        let store: Vec<&str> = rows
+            .into_iter()
+            .filter(|row| row.contains("StoreError") || row.contains("Wrapped"))
+            .collect();
+        assert_eq!(
+            store,
+            [
+                "| E3001 | StoreError::Missing | The key is not in the store. |",
+                "| E3002 | StoreError::Full |  |",
+            ]
+        );
+        assert_eq!(StoreError::Full.code(), "E3002");
+        assert_eq!(StoreError::Missing.code(), "E3001");
+        assert_eq!(Wrapped(()).code(), "E3003");
+    }
+
+    #[cfg(feature = "derive")]
+    #[traced_test]
+    #[test]
+    fn test_instrument() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        #[err_trail::instrument]
+        fn parse(path: &str, span: u8) -> Result<u8, std::num::ParseIntError> {
+            let value = path.parse::<u8>()?;
+            Ok(value + span)
+        }
+
+        #[err_trail::instrument(level = warn, name = "lookup", skip(key))]
+        fn get(key: &str, id: u32) -> Option<u32> {
+            if key.is_empty() {
+                return None;
+            }
+            Some(id)
+        }
+
+        #[err_trail::instrument(skip_all)]
+        async fn fetch(url: &str) -> Result<(), String> {
+            std::future::ready(()).await;
+            Err(format!("{} timed out", url))
+        }
+
+        struct Cache(Vec<u32>);
+
+        impl Cache {
+            #[err_trail::instrument]
+            fn get_mut(&mut self, index: usize) -> Option<&mut u32> {
+                self.0.get_mut(index)
+            }
+        }
+
+        #[err_trail::instrument]
+        fn evens(max: &str) -> Result<impl Iterator<Item = u32>, std::num::ParseIntError> {
+            let max = max.parse::<u32>()?;
+            Ok((0..max).filter(|n| n % 2 == 0))
+        }
+
+        assert_eq!(parse("1", 2), Ok(3));
+        assert!(!logs_contain("parse{"));
+        assert!(parse("a", 2).is_err());
+        assert!(logs_contain(
+            r#"ERROR test_instrument:parse{path="a" span=2}: r#mod::tracing: invalid digit"#
+        ));
+
+        assert_eq!(get("", 7), None);
+        assert!(logs_contain(
+            "WARN test_instrument:lookup{id=7}: r#mod::tracing: returned None"
+        ));
+
+        let mut future = Box::pin(fetch("db"));
+        let poll = future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()));
+        assert!(matches!(poll, Poll::Ready(Err(_))));
+        assert!(logs_contain(
+            "ERROR test_instrument:fetch: r#mod::tracing: db timed out"
+        ));
+
+        let mut cache = Cache(vec![1]);
+        *cache.get_mut(0).unwrap() += 1;
+        assert_eq!(cache.0, [2]);
+        assert!(cache.get_mut(1).is_none());
+        assert!(logs_contain(
+            "ERROR test_instrument:get_mut{index=1}: r#mod::tracing: returned None"
+        ));
+
+        assert_eq!(evens("5").unwrap().collect::<Vec<_>>(), [0, 2, 4]);
+        assert!(evens("x").is_err());
+        assert!(logs_contain(
+            r#"ERROR test_instrument:evens{max="x"}: r#mod::tracing: invalid digit"#
+        ));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_once() {
+        use err_trail::{Once, reset_call_sites, warn_once};
+
+        let _clock = crate::test_clock::lock();
+        let log = || {
+            warn_once!(attempt = 1; "misconfigured {}", "cache");
+            let _ = Err::<(), _>("no cache").error(Once(()));
+            let _ = None::<u8>.info(Once("missing"));
+            let _ = Err::<(), _>("method once").warn_once(());
+            let _ = None::<u8>.log_once(Level::Debug, "none once");
+            let _ = Err::<(), _>("error once").error_once(());
+            let _ = None::<u8>.trace_once("trace once");
+        };
+        for _ in 0..3 {
+            log();
+        }
+        let count = |lines: &[&str], expected: &str| {
+            lines.iter().filter(|line| line.contains(expected)).count()
+        };
+        logs_assert(|lines| {
+            assert_eq!(count(lines, "misconfigured cache attempt=1"), 1);
+            assert_eq!(count(lines, "ERROR test_once: err_trail: no cache"), 1);
+            assert_eq!(count(lines, "INFO test_once: err_trail: missing"), 1);
+            assert_eq!(count(lines, "WARN test_once: err_trail: method once"), 1);
+            assert_eq!(count(lines, "DEBUG test_once: err_trail: none once"), 1);
+            assert_eq!(count(lines, "ERROR test_once: err_trail: error once"), 1);
+            assert_eq!(count(lines, "TRACE test_once: err_trail: trace once"), 1);
+            Ok(())
+        });
+
+        reset_call_sites();
+        log();
+        logs_assert(|lines| {
+            assert_eq!(count(lines, "misconfigured cache"), 2);
+            assert_eq!(count(lines, "no cache"), 2);
+            assert_eq!(count(lines, "method once"), 2);
+            Ok(())
+        });
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_throttled() {
+        use err_trail::{Throttled, warn_throttled};
+        use std::time::Duration;
+
+        let _clock = crate::test_clock::lock();
+        let log = || {
+            warn_throttled!(per = 10s, max = 2, "throttled macro");
+            let _ =
+                Err::<(), _>("throttled input").error(Throttled::new((), Duration::from_secs(10)));
+            let _ = None::<u8>.warn_throttled(Duration::from_secs(10), 2, "throttled method");
+        };
+        for _ in 0..5 {
+            log();
+        }
+        crate::test_clock::advance(Duration::from_secs(10));
+        log();
+
+        let count = |lines: &[&str], expected: &str| {
+            lines.iter().filter(|line| line.contains(expected)).count()
+        };
+        logs_assert(|lines| {
+            assert_eq!(count(lines, "throttled macro"), 3);
+            assert_eq!(
+                count(lines, "throttled macro (suppressed 3 similar messages)"),
+                1
+            );
+            assert_eq!(count(lines, "throttled input"), 2);
+            assert_eq!(
+                count(lines, "throttled input (suppressed 4 similar messages)"),
+                1
+            );
+            assert_eq!(
+                count(lines, "WARN test_throttled: err_trail: throttled method"),
+                3
+            );
+            assert_eq!(
+                count(lines, "throttled method (suppressed 3 similar messages)"),
+                1
+            );
+            Ok(())
+        });
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_sampling() {
+        use err_trail::{EveryN, Sampled, debug_sampled, trace_every_n};
+
+        let _clock = crate::test_clock::lock();
+        for i in 0..10 {
+            trace_every_n!(3, "every n macro {}", i);
+            let _ = Err::<(), _>("every n input").warn(EveryN::new((), 5));
+            debug_sampled!(1.0, "sampled macro");
+            debug_sampled!(0.0, "never logged");
+            let _ = None::<u8>.info(Sampled::new("sampled input", 1.0));
+            let _ = Err::<(), _>("every n method").trace_every_n(4, ());
+            let _ = None::<u8>.log_sampled(Level::Info, 1.0, "sampled method");
+            let _ = None::<u8>.debug_every_n(2, "every n debug");
+            let _ = Err::<(), _>("sampled warn").warn_sampled(1.0, ());
+        }
+
+        assert!(logs_contain(
+            "every n macro 0 sample_rate=0.3333333333333333"
+        ));
+        assert!(logs_contain("every n macro 9"));
+        assert!(!logs_contain("every n macro 1"));
+        assert!(logs_contain("sample_rate=0.2"));
+        assert!(logs_contain("sample_rate=1.0"));
+        assert!(!logs_contain("never logged"));
+        let count = |lines: &[&str], expected: &str| {
+            lines.iter().filter(|line| line.contains(expected)).count()
+        };
+        logs_assert(|lines| {
+            assert_eq!(count(lines, "every n macro"), 4);
+            assert_eq!(count(lines, "every n input"), 2);
+            assert_eq!(count(lines, "sampled macro"), 10);
+            assert_eq!(count(lines, "sampled input"), 10);
+            assert_eq!(
+                count(lines, "TRACE test_sampling: err_trail: every n method"),
+                3
+            );
+            assert_eq!(
+                count(lines, "INFO test_sampling: err_trail: sampled method"),
+                10
+            );
+            assert_eq!(
+                count(lines, "DEBUG test_sampling: err_trail: every n debug"),
+                5
+            );
+            assert_eq!(
+                count(lines, "WARN test_sampling: err_trail: sampled warn"),
+                10
+            );
+            Ok(())
+        });
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_escalating() {
+        use std::time::Duration;
+
+        let _clock = crate::test_clock::lock();
+        let refresh = |result: Result<(), &str>| {
+            let _ = result.warn_escalating(3, Duration::from_secs(60), "cache refresh failed");
+        };
+        for result in [
+            Err("timeout"),
+            Err("timeout"),
+            Err("timeout"),
+            Err("timeout"),
+            Ok(()),
+        ] {
+            refresh(result);
+        }
+        refresh(Err("timeout"));
+        refresh(Err("timeout"));
+        crate::test_clock::advance(Duration::from_secs(60));
+        refresh(Err("timeout"));
+        let _ = None::<u8>.warn_escalating(1, Duration::from_secs(60), "no replica");
+
+        assert!(logs_contain("ERROR test_escalating: err_trail: no replica"));
+        let count = |lines: &[&str], level: &str| {
+            let lines = lines
+                .iter()
+                .filter(|line| line.contains("cache refresh failed"));
+            lines.filter(|line| line.contains(level)).count()
+        };
+        logs_assert(|lines| {
+            assert_eq!(count(lines, "WARN"), 5);
+            assert_eq!(count(lines, "ERROR"), 2);
+            Ok(())
+        });
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_escalating_outage() {
+        use std::time::Duration;
+
+        let _clock = crate::test_clock::lock();
+        let refresh = |result: Result<(), &str>| {
+            let _ = result.warn_escalating(3, Duration::from_secs(10), "replica unreachable");
+        };
+        // Failing every 4 seconds for a minute, well past the window, stays escalated.
+        for _ in 0..15 {
+            refresh(Err("timeout"));
+            crate::test_clock::advance(Duration::from_secs(4));
+        }
+        refresh(Ok(()));
+        refresh(Err("timeout"));
+
+        logs_assert(|lines| {
+            let levels: Vec<&str> = lines
+                .iter()
+                .filter(|line| line.contains("replica unreachable"))
+                .map(|line| if line.contains("ERROR") { "E" } else { "W" })
+                .collect();
+            let mut expected = vec!["W", "W"];
+            expected.extend(["E"; 13]);
+            expected.push("W");
+            assert_eq!(levels, expected);
+            Ok(())
+        });
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_none_unit() {
+        let _ = None::<u32>.warn(());
+
+        assert!(logs_contain(&format!(
+            "err_trail: expected Some(u32) at err_trail/tests/mod.rs:{}",
+            line!() - 4
+        )));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_display_inputs() {
+        use err_trail::Msg;
+        use std::fmt::Display;
+
+        let message: &dyn Display = &404;
+        let _ = Err::<(), ()>(()).error(format_args!("code {}", 500));
+        let _ = None::<()>.warn(message);
+        let _ = Err::<(), ()>(()).info(Msg(std::path::Path::new("/tmp/a").display()));
+
+        assert!(logs_contain(
+            "ERROR test_display_inputs: err_trail: code 500"
+        ));
+        assert!(logs_contain("WARN test_display_inputs: err_trail: 404"));
+        assert!(logs_contain("INFO test_display_inputs: err_trail: /tmp/a"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[traced_test]
+    #[test]
+    fn test_owned_inputs() {
+        use std::borrow::Cow;
+
+        let _ = Err::<(), ()>(()).error(format!("owned {}", 1));
+        let _ = None::<()>.warn(Cow::Borrowed("borrowed"));
+
+        assert!(logs_contain("ERROR test_owned_inputs: err_trail: owned 1"));
+        assert!(logs_contain("WARN test_owned_inputs: err_trail: borrowed"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_with_closure() {
+        let _ =
+            Err::<(), _>(String::from("disk full")).error_with(|e| format!("save failed: {}", e));
+        let _ = Err::<(), _>(3u8).log_with(Level::Warn, |code| format!("exit code {}", code));
+
+        assert!(logs_contain(
+            "ERROR test_with_closure: err_trail: save failed: disk full"
+        ));
+        assert!(logs_contain(
+            "WARN test_with_closure: err_trail: exit code 3"
+        ));
+    }
+
+    #[cfg(feature = "type-name")]
+    #[traced_test]
+    #[test]
+    fn test_type_name() {
+        let _ = Err::<(), _>(std::fmt::Error).error(());
+        let _ = None::<u8>.warn("no byte");
+
+        assert!(logs_contain("error.type=\"core::fmt::Error\""));
+        assert!(logs_contain("no byte"));
+        assert!(logs_contain("error.type=\"u8\""));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_log_macro() {
+        let level: Level = "info".parse().unwrap();
+        log!(level, "runtime info: {}", 1);
+        log!(Level::Error, target: "runtime", code = 7; "runtime error");
+
+        assert!(logs_contain("INFO"));
+        assert!(logs_contain("runtime info: 1"));
+        assert!(logs_contain("runtime: runtime error code=7"));
+        assert_eq!(tracing::Level::from(Level::Warn), tracing::Level::WARN);
+        assert_eq!(Level::from(tracing::Level::TRACE), Level::Trace);
+    }
+
     #[traced_test]
     #[test]
     fn test_macro_multiple_args() {
@@ -233,6 +1154,7 @@ mod log {
     use err_trail::{ErrContext, NoneContext, debug, error, info, trace, warn};
     use flaky_test::flaky_test;
     use lazy_static::lazy_static;
+    use log::kv::{Key, Value, VisitSource};
     use log::{Level, Metadata, Record};
     use std::sync::{Arc, Mutex};
 
@@ -240,6 +1162,15 @@ mod log {
         logs: Arc<Mutex<Vec<String>>>,
     }
 
+    struct KeyValues(String);
+
+    impl<'kvs> VisitSource<'kvs> for KeyValues {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+            self.0.push_str(&format!(" {}={}", key, value));
+            Ok(())
+        }
+    }
+
     impl log::Log for TestLogger {
         fn enabled(&self, metadata: &Metadata) -> bool {
             metadata.level() <= Level::Trace
@@ -247,8 +1178,17 @@ mod log {
 
         fn log(&self, record: &Record) {
             if self.enabled(record.metadata()) {
+                let mut key_values = KeyValues(String::new());
+                record.key_values().visit(&mut key_values).unwrap();
                 let mut logs = self.logs.lock().unwrap();
-                logs.push(format!("{}", record.args()));
+                logs.push(format!(
+                    "[{}] {}:{} {}{}",
+                    record.target(),
+                    record.file().unwrap_or_default(),
+                    record.line().unwrap_or_default(),
+                    record.args(),
+                    key_values.0
+                ));
             }
         }
 
@@ -475,6 +1415,338 @@ mod log {
         assert!(logs_contain("Lazy trace context"));
     }
 
+    #[flaky_test]
+    fn test_caller_location() {
+        clear_logs();
+        let result: Result<(), &str> = Err("error");
+        let _ = result.error("Located error");
+        let line = line!() - 1;
+
+        assert!(logs_contain(&format!(
+            "tests/mod.rs:{} Located error",
+            line
+        )));
+    }
+
+    #[flaky_test]
+    fn test_option_caller_location() {
+        clear_logs();
+        let option: Option<()> = None;
+        let _ = option.warn(|| "Located none");
+        let line = line!() - 1;
+
+        assert!(logs_contain(&format!("tests/mod.rs:{} Located none", line)));
+    }
+
+    #[flaky_test]
+    fn test_macro_fields() {
+        clear_logs();
+        let user_id = 7;
+        let error = "refused";
+        error!(user_id = user_id, retries = 3, cause = %error, path = ?"/tmp"; "connect failed: {}", error);
+
+        assert!(logs_contain(
+            "connect failed: refused user_id=7 retries=3 cause=refused path=\"/tmp\""
+        ));
+    }
+
+    #[flaky_test]
+    fn test_macro_metadata() {
+        clear_logs();
+        error!(target: "db::pool", "pool exhausted");
+        warn!(name: "retry_event", target: "db::pool", attempt = 2; "retrying");
+        info!(parent: None, "checked out");
+
+        assert!(logs_contain("[db::pool] err_trail/tests/mod.rs"));
+        assert!(logs_contain("retrying attempt=2"));
+        assert!(logs_contain("mod::log] err_trail/tests/mod.rs"));
+    }
+
+    #[flaky_test]
+    fn test_trail() {
+        clear_logs();
+        let result: Result<u8, &str> = Err("boom");
+        let _ = err_trail::trail!(warn, result, "failed to load {}", "a");
+        let line = line!() - 1;
+        let _ = err_trail::trail!(info, None::<u8>);
+
+        assert!(logs_contain(&format!(
+            "mod::log] err_trail/tests/mod.rs:{} failed to load a: boom",
+            line
+        )));
+        assert!(logs_contain("mod::log] err_trail/tests/mod.rs:"));
+        assert!(logs_contain(" expected Some("));
+    }
+
+    #[flaky_test]
+    fn test_log_level() {
+        clear_logs();
+        let result: Result<(), &str> = Err("error");
+        let _ = result.log(err_trail::Level::Warn, |e: &&str| {
+            format!("Runtime level: `{}`", e)
+        });
+        let option: Option<()> = None;
+        let _ = option.log(err_trail::Level::Debug, "Runtime level none");
+
+        assert!(logs_contain("Runtime level: `error`"));
+        assert!(logs_contain("Runtime level none"));
+        assert_eq!(Level::from(err_trail::Level::Warn), Level::Warn);
+    }
+
+    #[flaky_test]
+    fn test_fatal() {
+        clear_logs();
+        let result: Result<u32, &str> = Err("corrupt");
+        let panic = std::panic::catch_unwind(|| result.fatal(|e: &&str| format!("Fatal: `{}`", e)))
+            .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Fatal: `corrupt`");
+        assert!(logs_contain("Fatal: `corrupt`"));
+
+        let panic = std::panic::catch_unwind(|| err_trail::fatal!(target: "fatal", "Fatal macro"))
+            .unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "Fatal macro");
+        assert!(logs_contain("[fatal]"));
+    }
+
+    #[flaky_test]
+    fn test_chain() {
+        use crate::chain_error::layered;
+        use err_trail::Chain;
+
+        clear_logs();
+        let _ = Err::<(), _>(layered()).error(Chain);
+
+        assert!(logs_contain("outer: middle: root"));
+    }
+
+    #[flaky_test]
+    fn test_debug_inputs() {
+        use err_trail::{Dbg, PrettyDbg};
+
+        clear_logs();
+        let _ = Err::<(), _>(vec![7]).error(Dbg);
+        let _ = Err::<(), _>(vec![8]).warn(PrettyDbg);
+
+        assert!(logs_contain("[7]"));
+        assert!(logs_contain("[\n    8,\n]"));
+    }
+
+    #[flaky_test]
+    fn test_context() {
+        clear_logs();
+        let _ = Err::<(), _>("file not found").error(err_trail::context("failed to load config"));
+
+        assert!(logs_contain("failed to load config: file not found"));
+    }
+
+    #[flaky_test]
+    fn test_consuming() {
+        use err_trail::Level;
+
+        clear_logs();
+        Err::<(), _>("discarded").log_and_discard(Level::Warn, ());
+        assert_eq!(None::<u8>.unwrap_or_log(2, Level::Error, "or"), 2);
+        let panic = std::panic::catch_unwind(|| {
+            Err::<u8, _>("corrupt").expect_log(Level::Error, err_trail::context("expecting"))
+        })
+        .unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "expecting: corrupt"
+        );
+
+        assert!(logs_contain("discarded"));
+        assert!(logs_contain("or"));
+        assert!(logs_contain("expecting: corrupt"));
+    }
+
+    #[flaky_test]
+    fn test_success_side() {
+        clear_logs();
+        let _ = Ok::<_, ()>("user 1").info_ok(());
+        let _ = Some("stale").warn_some(());
+
+        assert!(logs_contain("user 1"));
+        assert!(logs_contain("stale"));
+    }
+
+    #[flaky_test]
+    fn test_predicates() {
+        clear_logs();
+        let _ = Err::<(), _>(7).warn_if(|code| *code == 7, "matched");
+        let _ = Err::<(), _>(8).warn_unless(|code| *code == 8, "excluded");
+
+        assert!(logs_contain("matched"));
+        assert!(!logs_contain("excluded"));
+    }
+
+    #[flaky_test]
+    fn test_once() {
+        use err_trail::{Once, error_once};
+
+        clear_logs();
+        for _ in 0..3 {
+            error_once!("log once macro");
+            let _ = Err::<(), _>("log once input").warn(Once(()));
+            let _ = None::<u8>.warn_once("log once method");
+        }
+
+        let logs = LOGS.lock().unwrap();
+        assert_eq!(
+            logs.iter()
+                .filter(|log| log.contains("log once macro"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            logs.iter()
+                .filter(|log| log.contains("log once input"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            logs.iter()
+                .filter(|log| log.contains("log once method"))
+                .count(),
+            1
+        );
+    }
+
+    #[flaky_test]
+    fn test_throttled() {
+        use err_trail::{Once, Throttled, error_throttled};
+        use std::time::Duration;
+
+        let _clock = crate::test_clock::lock();
+        clear_logs();
+        let log = || {
+            error_throttled!(per = 500ms, "log throttled macro");
+            let _ = None::<u8>.warn(Once(Throttled::new(
+                "log throttled input",
+                Duration::from_secs(1),
+            )));
+            let _ = Err::<(), _>("log throttled method").log_throttled(
+                err_trail::Level::Info,
+                Duration::from_secs(1),
+                1,
+                (),
+            );
+        };
+        log();
+        log();
+        crate::test_clock::advance(Duration::from_secs(1));
+        log();
+
+        let logs = LOGS.lock().unwrap().clone();
+        let count = |expected: &str| logs.iter().filter(|log| log.contains(expected)).count();
+        assert_eq!(count("log throttled macro"), 2);
+        assert_eq!(
+            count("log throttled macro (suppressed 1 similar messages)"),
+            1
+        );
+        assert_eq!(count("log throttled input"), 1);
+        assert_eq!(count("log throttled method"), 2);
+        assert_eq!(
+            count("log throttled method (suppressed 1 similar messages)"),
+            1
+        );
+    }
+
+    #[flaky_test]
+    fn test_sampling() {
+        use err_trail::{EveryN, Sampled, debug_sampled, seed_sampling};
+
+        let _clock = crate::test_clock::lock();
+        let run = || {
+            seed_sampling(7);
+            for i in 0..64 {
+                debug_sampled!(0.5, "log sampled {}", i);
+            }
+            let logs = LOGS.lock().unwrap();
+            let sampled = logs.iter().filter(|log| log.contains("log sampled "));
+            sampled.cloned().collect::<Vec<_>>()
+        };
+        clear_logs();
+        let first = run();
+        clear_logs();
+        let second = run();
+        assert_eq!(first, second);
+        assert!(first.len() > 8 && first.len() < 56);
+        assert!(first[0].ends_with(" sample_rate=0.5"));
+
+        clear_logs();
+        for _ in 0..8 {
+            let _ = Err::<(), _>("x").trace(Sampled::new(EveryN::new("log every n", 4), 1.0));
+        }
+        let logs = LOGS.lock().unwrap().clone();
+        let every_n = logs.iter().filter(|log| log.contains("log every n"));
+        assert_eq!(every_n.clone().count(), 2);
+        assert!(
+            every_n
+                .clone()
+                .all(|log| log.ends_with(" sample_rate=0.25"))
+        );
+
+        clear_logs();
+        for _ in 0..8 {
+            let _ = None::<u8>.debug_sampled(1.0, "log sampled method");
+        }
+        let logs = LOGS.lock().unwrap().clone();
+        let sampled = logs.iter().filter(|log| log.contains("log sampled method"));
+        assert_eq!(sampled.clone().count(), 8);
+        assert!(sampled.clone().all(|log| log.ends_with(" sample_rate=1")));
+    }
+
+    #[flaky_test]
+    fn test_none_unit() {
+        clear_logs();
+        let _ = None::<u32>.warn(());
+
+        assert!(logs_contain(&format!(
+            "expected Some(u32) at err_trail/tests/mod.rs:{}",
+            line!() - 4
+        )));
+    }
+
+    #[flaky_test]
+    fn test_display_inputs() {
+        clear_logs();
+        let _ = Err::<(), ()>(()).error(format_args!("code {}", 500));
+        let _ = None::<()>.warn(err_trail::Msg(404));
+
+        assert!(logs_contain("code 500"));
+        assert!(logs_contain("404"));
+    }
+
+    #[flaky_test]
+    fn test_with_closure() {
+        clear_logs();
+        let _ =
+            Err::<(), _>(String::from("disk full")).error_with(|e| format!("save failed: {}", e));
+
+        assert!(logs_contain("save failed: disk full"));
+    }
+
+    #[cfg(feature = "type-name")]
+    #[flaky_test]
+    fn test_type_name() {
+        clear_logs();
+        let _ = Err::<(), _>(std::fmt::Error).error(());
+        let _ = None::<u8>.warn("no byte");
+
+        assert!(logs_contain("error.type=core::fmt::Error"));
+        assert!(logs_contain("no byte error.type=u8"));
+    }
+
+    #[flaky_test]
+    fn test_log_macro() {
+        clear_logs();
+        err_trail::log!(err_trail::Level::Error, target: "runtime", code = 7; "runtime error");
+
+        assert!(logs_contain("[runtime]"));
+        assert!(logs_contain("runtime error code=7"));
+    }
+
     #[test]
     fn test_macros_to_log_backend() {
         clear_logs();
@@ -0,0 +1,47 @@
+//! Without the `std` feature and before `set_clock`, there is no clock, which is process-wide, so
+//! it is tested in its own binary.
+#![cfg(all(feature = "log", not(feature = "std")))]
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use err_trail::{ErrContext, warn_throttled};
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let file = record.file().unwrap_or_default();
+        LOGS.lock()
+            .unwrap()
+            .push(format!("{}: {}", file, record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn test_no_clock_warns_once() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    for _ in 0..3 {
+        warn_throttled!(per = 10s, "throttled");
+        let _ = Err::<(), _>("escalating").warn_escalating(5, Duration::from_secs(1), ());
+    }
+
+    let logs = LOGS.lock().unwrap();
+    let count = |expected: &str| logs.iter().filter(|log| log.contains(expected)).count();
+    // At the first call site that needed a clock.
+    assert_eq!(count("tests/no_clock.rs: no clock is set"), 1);
+    assert_eq!(count("no clock is set"), 1);
+    assert_eq!(count("tests/no_clock.rs: throttled"), 3);
+    assert_eq!(count("tests/no_clock.rs: escalating"), 3);
+}
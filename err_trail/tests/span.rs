@@ -0,0 +1,105 @@
+//! With the `single-threaded` feature, the entered spans are shared by the whole process, so spans
+//! are tested in their own binary, one test at a time, to not prefix the events of other tests.
+#![cfg(any(feature = "tracing", feature = "log"))]
+
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[cfg(feature = "tracing")]
+mod tracing {
+    use err_trail::{ErrContext, Level, span};
+    use tracing_test::traced_test;
+
+    #[traced_test]
+    #[test]
+    fn test_span() {
+        let _serial = super::SERIAL
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        span!(Level::Info, "request", id = 7, path = %"/health").in_scope(|| {
+            let _ = Err::<(), _>("query failed").error(());
+        });
+        assert!(logs_contain("request{id=7 path=/health}"));
+        assert!(logs_contain("query failed"));
+    }
+}
+
+#[cfg(feature = "log")]
+mod log {
+    use err_trail::{Level, NoneContext, Span, span, warn};
+    use log::{Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct TestLogger;
+
+    impl Log for TestLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            LOGS.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn take_logs() -> Vec<String> {
+        std::mem::take(&mut *LOGS.lock().unwrap())
+    }
+
+    #[test]
+    fn test_span() {
+        let _serial = super::SERIAL
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let outer = span!(Level::Info, "outer", a = 1, b = ?"two");
+        {
+            let _outer = outer.enter();
+            warn!("in outer");
+            span!(Level::Debug, "inner").in_scope(|| {
+                let _ = None::<u8>.error("in inner");
+            });
+            warn!("back in outer");
+        }
+        warn!("outside");
+        Span::none().in_scope(|| warn!("in none"));
+
+        // Disabled levels create no span.
+        log::set_max_level(log::LevelFilter::Info);
+        span!(Level::Debug, "hidden").in_scope(|| warn!("in hidden"));
+
+        if cfg!(any(feature = "std", feature = "single-threaded")) {
+            assert_eq!(
+                take_logs(),
+                [
+                    r#"outer{a=1 b="two"}: in outer"#,
+                    r#"outer{a=1 b="two"}:inner: in inner"#,
+                    r#"outer{a=1 b="two"}: back in outer"#,
+                    "outside",
+                    "in none",
+                    "in hidden",
+                ]
+            );
+        } else {
+            // Without a scope per thread, nothing is prefixed.
+            assert_eq!(
+                take_logs(),
+                [
+                    "in outer",
+                    "in inner",
+                    "back in outer",
+                    "outside",
+                    "in none",
+                    "in hidden",
+                ]
+            );
+        }
+    }
+}
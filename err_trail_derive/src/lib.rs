@@ -0,0 +1,378 @@
+//! Derive and attribute macros for [err_trail](https://docs.rs/err_trail), enabled with its
+//! `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, ExprLit, FnArg, Ident, ItemFn, Lit, LitStr, Meta,
+    Pat, ReturnType, Token, Type, parse_macro_input,
+};
+
+/// Derives `err_trail::Severity`.
+///
+/// The level is set with `#[severity(error)]`, `#[severity(warn)]`, `#[severity(info)]`,
+/// `#[severity(debug)]` or `#[severity(trace)]`. On an enum, each variant can set its own level,
+/// falling back to the level on the enum itself, or "error" if there is none.
+#[proc_macro_derive(Severity, attributes(severity))]
+pub fn derive_severity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    severity(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn severity(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let default = level_attr(&input.attrs)?.unwrap_or_else(|| quote!(::err_trail::Level::Error));
+    let body = match &input.data {
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let level = level_attr(&variant.attrs)?.unwrap_or_else(|| default.clone());
+                    Ok(quote!(Self::#ident { .. } => #level,))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            // Through `*self`, so an enum without variants matches exhaustively.
+            quote! {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Struct(_) => default,
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`Severity` cannot be derived for unions",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::err_trail::Severity for #ident #ty_generics #where_clause {
+            fn level(&self) -> ::err_trail::Level {
+                #body
+            }
+        }
+    })
+}
+
+/// The level of a `#[severity(..)]` attribute, if present.
+fn level_attr(attrs: &[Attribute]) -> syn::Result<Option<TokenStream2>> {
+    let Some(attr) = single_attr(attrs, "severity")? else {
+        return Ok(None);
+    };
+    let level: Ident = attr.parse_args()?;
+    let variant = level_variant(&level)?;
+    Ok(Some(quote!(::err_trail::Level::#variant)))
+}
+
+/// The attribute with the given name, if present. It may only be given once.
+fn single_attr<'a>(attrs: &'a [Attribute], name: &str) -> syn::Result<Option<&'a Attribute>> {
+    let mut found = attrs.iter().filter(|attr| attr.path().is_ident(name));
+    let first = found.next();
+    if let Some(duplicate) = found.next() {
+        let mut error = Error::new_spanned(duplicate, format!("duplicate `#[{}]` attribute", name));
+        error.combine(Error::new_spanned(first, "first used here"));
+        return Err(error);
+    }
+    Ok(first)
+}
+
+/// Derives `err_trail::ErrorCode`.
+///
+/// The code is set with `#[code("E1042")]` on a struct, or on every variant of an enum. Codes must
+/// be unique within the type. Doc comments on the struct or variants become the descriptions.
+/// With the `catalog` feature of err_trail, the codes of non-generic types are registered for
+/// `Catalog::all`.
+#[proc_macro_derive(ErrorCode, attributes(code))]
+pub fn derive_error_code(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    error_code(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn error_code(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let mut codes: Vec<(LitStr, String, String)> = Vec::new();
+    let body = match &input.data {
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let variant_ident = &variant.ident;
+                let code = code_attr(&variant.attrs)?.ok_or_else(|| {
+                    Error::new_spanned(variant_ident, "missing `#[code(\"..\")]` attribute")
+                })?;
+                if let Some((first, _, _)) = codes
+                    .iter()
+                    .find(|(other, _, _)| other.value() == code.value())
+                {
+                    let mut error = Error::new_spanned(&code, "duplicate error code");
+                    error.combine(Error::new_spanned(first, "first used here"));
+                    return Err(error);
+                }
+                arms.push(quote!(Self::#variant_ident { .. } => #code,));
+                codes.push((
+                    code,
+                    format!("{}::{}", ident, variant_ident),
+                    doc_attr(&variant.attrs),
+                ));
+            }
+            quote! {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Struct(_) => {
+            let code = code_attr(&input.attrs)?
+                .ok_or_else(|| Error::new_spanned(ident, "missing `#[code(\"..\")]` attribute"))?;
+            codes.push((code.clone(), ident.to_string(), doc_attr(&input.attrs)));
+            quote!(#code)
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "`ErrorCode` cannot be derived for unions",
+            ));
+        }
+    };
+
+    let infos = codes.iter().map(|(code, name, description)| {
+        quote! {
+            ::err_trail::CodeInfo {
+                code: #code,
+                name: #name,
+                description: #description,
+            }
+        }
+    });
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // A static cannot depend on generic parameters, so generic types are not registered.
+    let register = (cfg!(feature = "catalog") && input.generics.params.is_empty()).then(|| {
+        quote! {
+            const _: () = {
+                #[::err_trail::__linkme::distributed_slice(::err_trail::__CODES)]
+                #[linkme(crate = ::err_trail::__linkme)]
+                static CODES: &'static [::err_trail::CodeInfo] =
+                    <#ident as ::err_trail::ErrorCode>::CODES;
+            };
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::err_trail::ErrorCode for #ident #ty_generics #where_clause {
+            const CODES: &'static [::err_trail::CodeInfo] = &[#(#infos),*];
+
+            fn code(&self) -> &'static str {
+                #body
+            }
+        }
+
+        #register
+    })
+}
+
+/// The code of a `#[code("..")]` attribute, if present.
+fn code_attr(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    single_attr(attrs, "code")?
+        .map(|attr| attr.parse_args())
+        .transpose()
+}
+
+/// The doc comment lines, trimmed and joined with spaces.
+fn doc_attr(attrs: &[Attribute]) -> String {
+    let mut doc = String::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("doc")) {
+        if let Meta::NameValue(meta) = &attr.meta
+            && let Expr::Lit(ExprLit {
+                lit: Lit::Str(line),
+                ..
+            }) = &meta.value
+        {
+            let line = line.value();
+            let line = line.trim();
+            if !line.is_empty() {
+                if !doc.is_empty() {
+                    doc.push(' ');
+                }
+                doc.push_str(line);
+            }
+        }
+    }
+    doc
+}
+
+/// Logs the `Err` or `None` returned from a function, within a span named after it that records
+/// its arguments with `Debug`, e.g. "parse{path=\"a.toml\"}: invalid digit". `async` functions are
+/// supported. Without a backend feature, the function is left as is.
+///
+/// - `level = warn` sets the level, "error" by default.
+/// - `name = "load"` sets the name of the span, the name of the function by default.
+/// - `skip(password, body)` does not record those arguments, e.g. as they are not `Debug`.
+///   `skip_all` records none. `self` and destructured arguments are never recorded.
+///
+/// The arguments are recorded before the function runs, when the level is enabled.
+#[proc_macro_attribute]
+pub fn instrument(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    let parser = Punctuated::<Meta, Token![,]>::parse_terminated;
+    let args = parse_macro_input!(args with parser);
+    instrument_fn(args, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn instrument_fn(args: Punctuated<Meta, Token![,]>, mut item: ItemFn) -> syn::Result<TokenStream2> {
+    let mut level = Ident::new("error", Span::call_site());
+    let mut name = LitStr::new(&item.sig.ident.to_string(), item.sig.ident.span());
+    let mut skip = Vec::new();
+    let mut skip_all = false;
+    for arg in args {
+        match &arg {
+            Meta::NameValue(meta) if meta.path.is_ident("level") => {
+                let value = &meta.value;
+                level = syn::parse2(quote!(#value))?;
+                level_variant(&level)?;
+            }
+            Meta::NameValue(meta) if meta.path.is_ident("name") => {
+                let value = &meta.value;
+                name = syn::parse2(quote!(#value))?;
+            }
+            Meta::List(meta) if meta.path.is_ident("skip") => {
+                skip.extend(
+                    meta.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?,
+                );
+            }
+            Meta::Path(path) if path.is_ident("skip_all") => skip_all = true,
+            _ => {
+                return Err(Error::new_spanned(
+                    arg,
+                    "expected `level = ..`, `name = \"..\"`, `skip(..)` or `skip_all`",
+                ));
+            }
+        }
+    }
+
+    // Hygienic, so they do not shadow the arguments within the body.
+    let span = Ident::new("span", Span::mixed_site());
+    let result = Ident::new("result", Span::mixed_site());
+    let ReturnType::Type(_, output) = &item.sig.output else {
+        return Err(Error::new_spanned(
+            &item.sig,
+            "`instrument` requires a function returning `Result` or `Option`",
+        ));
+    };
+    let failed = match last_segment(output).as_deref() {
+        Some("Result") => quote! {
+            if let ::core::result::Result::Err(error) = &#result {
+                let _entered = #span.enter();
+                ::err_trail::#level!("{}", error);
+            }
+        },
+        Some("Option") => quote! {
+            if #result.is_none() {
+                let _entered = #span.enter();
+                ::err_trail::#level!("returned None");
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                output,
+                "`instrument` requires a function returning `Result` or `Option`",
+            ));
+        }
+    };
+    if let Some(constness) = &item.sig.constness {
+        return Err(Error::new_spanned(
+            constness,
+            "`instrument` does not support const functions",
+        ));
+    }
+    if !cfg!(any(feature = "tracing", feature = "log", feature = "defmt")) {
+        return Ok(quote!(#item));
+    }
+
+    let mut fields = Vec::new();
+    if !skip_all {
+        for input in &item.sig.inputs {
+            if let FnArg::Typed(input) = input
+                && let Pat::Ident(pat) = &*input.pat
+                && !skip.contains(&pat.ident)
+            {
+                let ident = &pat.ident;
+                fields.push(quote!(#ident = ?#ident));
+            }
+        }
+    }
+    let variant = level_variant(&level)?;
+    let block = &item.block;
+    let body = if item.sig.asyncness.is_some() {
+        quote!(async move #block.await)
+    } else {
+        // Called as a `FnOnce`, so the body can return borrows of the arguments, e.g. `&mut self`.
+        quote!(::err_trail::__call(move || #block))
+    };
+    // `impl Trait` is not allowed in a `let`, so it is left to inference. The rest of the type is
+    // kept, so `?` still knows which error to convert to.
+    let mut output = (**output).clone();
+    InferImplTrait.visit_type_mut(&mut output);
+    item.block = syn::parse2(quote! {{
+        let #span = ::err_trail::span!(::err_trail::Level::#variant, #name #(, #fields)*);
+        let #result: #output = #body;
+        #failed
+        #result
+    }})?;
+    Ok(quote!(#item))
+}
+
+/// Replaces every `impl Trait` in a type with `_`.
+struct InferImplTrait;
+
+impl VisitMut for InferImplTrait {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::ImplTrait(impl_trait) = ty {
+            *ty = Type::Infer(syn::TypeInfer {
+                underscore_token: Token![_](impl_trait.impl_token.span),
+            });
+        } else {
+            visit_mut::visit_type_mut(self, ty);
+        }
+    }
+}
+
+/// The variant of `err_trail::Level` for a level given as e.g. `warn`.
+fn level_variant(level: &Ident) -> syn::Result<TokenStream2> {
+    match level.to_string().as_str() {
+        "error" => Ok(quote!(Error)),
+        "warn" => Ok(quote!(Warn)),
+        "info" => Ok(quote!(Info)),
+        "debug" => Ok(quote!(Debug)),
+        "trace" => Ok(quote!(Trace)),
+        _ => Err(Error::new_spanned(
+            level,
+            "expected one of `error`, `warn`, `info`, `debug` or `trace`",
+        )),
+    }
+}
+
+/// The name of the last segment of a type path, e.g. "Result" for `io::Result<T>`.
+fn last_segment(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        Type::Paren(ty) => last_segment(&ty.elem),
+        Type::Group(ty) => last_segment(&ty.elem),
+        _ => None,
+    }
+}
@@ -0,0 +1 @@
+//! Tests of the `err_trail` macros expanded in another crate. See `tests/`.
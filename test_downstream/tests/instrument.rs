@@ -0,0 +1,68 @@
+use std::sync::{Mutex, Once};
+
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOGS.lock().unwrap().push(format!(
+            "[{} {}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+}
+
+fn logs_contain(expected: &str) -> bool {
+    LOGS.lock()
+        .unwrap()
+        .iter()
+        .any(|log| log.contains(expected))
+}
+
+#[err_trail::instrument]
+fn parse(path: &str, offset: u8) -> Result<u8, std::num::ParseIntError> {
+    let value = path.parse::<u8>()?;
+    Ok(value + offset)
+}
+
+#[err_trail::instrument(level = warn, name = "lookup", skip(key))]
+fn get(key: &str, id: u32) -> Option<u32> {
+    if key.is_empty() {
+        return None;
+    }
+    Some(id)
+}
+
+#[test]
+fn test_instrument() {
+    init();
+    assert_eq!(parse("1", 2), Ok(3));
+    assert!(!logs_contain("parse{"));
+    assert!(parse("x", 2).is_err());
+    assert!(logs_contain(
+        r#"[ERROR instrument] parse{path="x" offset=2}: invalid digit found in string"#
+    ));
+    assert_eq!(get("", 7), None);
+    assert!(logs_contain(
+        "[WARN instrument] lookup{id=7}: returned None"
+    ));
+}
@@ -0,0 +1,126 @@
+use std::sync::{Mutex, Once};
+
+use err_trail::{Level, debug, error, error_once, fatal, info, log, span, trace, trail, warn};
+use log::kv::{Key, Value, VisitSource};
+use log::{Log, Metadata, Record};
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct TestLogger;
+
+struct KeyValues(String);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.push_str(&format!(" {}={}", key, value));
+        Ok(())
+    }
+}
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let mut key_values = KeyValues(String::new());
+        record.key_values().visit(&mut key_values).unwrap();
+        LOGS.lock().unwrap().push(format!(
+            "[{} {}] {}{}",
+            record.level(),
+            record.target(),
+            record.args(),
+            key_values.0
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+}
+
+fn logs_contain(expected: &str) -> bool {
+    LOGS.lock()
+        .unwrap()
+        .iter()
+        .any(|log| log.contains(expected))
+}
+
+#[test]
+fn test_level_macros() {
+    init();
+    error!("downstream error {}", 1);
+    warn!("downstream warn {}", 2);
+    info!("downstream info {}", 3);
+    debug!("downstream debug {}", 4);
+    trace!("downstream trace {}", 5);
+    assert!(logs_contain("[ERROR macros] downstream error 1"));
+    assert!(logs_contain("[WARN macros] downstream warn 2"));
+    assert!(logs_contain("[INFO macros] downstream info 3"));
+    assert!(logs_contain("[DEBUG macros] downstream debug 4"));
+    assert!(logs_contain("[TRACE macros] downstream trace 5"));
+}
+
+#[test]
+fn test_fields_and_target() {
+    init();
+    warn!(target: "downstream::db", id = 7, path = %"/health", query = ?"select"; "downstream fields");
+    assert!(logs_contain(
+        r#"[WARN downstream::db] downstream fields id=7 path=/health query="select""#
+    ));
+}
+
+#[test]
+fn test_runtime_level() {
+    init();
+    let level: Level = "info".parse().unwrap();
+    log!(level, "downstream runtime level");
+    assert!(logs_contain("[INFO macros] downstream runtime level"));
+}
+
+#[test]
+fn test_once() {
+    init();
+    for attempt in 0..3 {
+        error_once!("downstream once {}", attempt);
+    }
+    assert!(logs_contain("downstream once 0"));
+    assert!(!logs_contain("downstream once 1"));
+}
+
+#[test]
+fn test_fatal() {
+    init();
+    let panic = std::panic::catch_unwind(|| fatal!("downstream fatal")).unwrap_err();
+    assert_eq!(panic.downcast_ref::<String>().unwrap(), "downstream fatal");
+    assert!(logs_contain("[ERROR macros] downstream fatal"));
+}
+
+#[test]
+fn test_trail() {
+    init();
+    let parsed = trail!(warn, "x".parse::<u8>(), "downstream trail {}", 1);
+    assert!(parsed.is_err());
+    assert!(logs_contain(
+        "[WARN macros] downstream trail 1: invalid digit found in string"
+    ));
+    let missing: Option<u8> = trail!(error, None);
+    assert!(missing.is_none());
+    assert!(logs_contain("[ERROR macros] expected Some("));
+}
+
+#[test]
+fn test_span() {
+    init();
+    let span = span!(Level::Info, "request", id = 7, path = %"/health");
+    span.in_scope(|| info!("downstream span"));
+    assert!(logs_contain(
+        "[INFO macros] request{id=7 path=/health}: downstream span"
+    ));
+}
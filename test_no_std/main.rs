@@ -1,7 +1,7 @@
 #![no_std]
 #![no_main]
 
-use err_trail::ErrContext;
+use err_trail::{Chain, ErrContext, Once};
 use exit_no_std::exit;
 
 #[unsafe(no_mangle)]
@@ -15,6 +15,9 @@ fn log() {
     let x: Result<u32, &str> = Err("error value");
     let _: Result<u32, &str> = x.error("context around");
     let _: Option<u32> = x.info(()).ok();
+    let y: Result<u32, TestError> = Err(TestError::new(1));
+    let _: Result<u32, TestError> = y.warn(Chain::caused_by().max_depth(3));
+    let _: Result<u32, TestError> = Err(TestError::new(2)).warn(Once(()));
 }
 
 #[cfg(not(test))]